@@ -5,19 +5,22 @@ use std::time::Duration;
 use tauri::path::BaseDirectory;
 use tauri::{AppHandle, Emitter, Manager, State};
 
+use super::dict::SelectedStarDict;
 use super::logics;
 use super::logics::config::{Config, PartialConfig};
+use super::logics::error::{Error, ErrorKind, Result};
+use super::logics::utils::FileWatcher;
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct Portable(pub bool);
 
 impl Portable {
-    pub fn new() -> Result<Self, String> {
+    pub fn new() -> Result<Self> {
         // 如果当前 exe 的旁边存在 config.toml，则认为是便携模式
         let config_path = logics::utils::current_exe_dir()?.join("config.toml");
         let portable = config_path
             .try_exists()
-            .map_err(|e| format!("failed to to detect if config.toml exists: {e}"))?;
+            .map_err(|e| Error::io("failed to to detect if config.toml exists", e))?;
         return Ok(Portable(portable));
     }
 }
@@ -29,13 +32,15 @@ impl ConfigPath {
     pub fn new(
         portable: bool,
         path_resolver: &tauri::path::PathResolver<impl tauri::Runtime>,
-    ) -> Result<Self, String> {
+    ) -> Result<Self> {
         let config_path = if portable {
             logics::utils::current_exe_dir()?.join("config.toml")
         } else {
             path_resolver
                 .app_config_dir()
-                .map_err(|e| format!("failed to resolve app config directory: {e}"))?
+                .map_err(|e| {
+                    Error::internal("failed to resolve app config directory").with_detail(e)
+                })?
                 .join("config.toml")
         };
         return Ok(ConfigPath(config_path.to_string_lossy().into_owned()));
@@ -46,34 +51,124 @@ impl ConfigPath {
 pub fn read_config(
     config_path: State<ConfigPath>,
     portable: State<Portable>,
+    stardict: State<SelectedStarDict>,
     app: AppHandle,
-) -> Result<Config, String> {
+) -> Result<Config> {
     let config_path: &Path = config_path.0.as_ref();
     let portable = portable.0;
     if !config_path
         .try_exists()
-        .map_err(|e| format!("failed to to detect if config.toml exists: {e}"))?
+        .map_err(|e| Error::io("failed to to detect if config.toml exists", e))?
     {
         if portable {
-            return Err("config.toml does not exist".to_string());
+            return Err(
+                Error::new(ErrorKind::ConfigMissing, "config.toml does not exist")
+                    .with_path(config_path),
+            );
         }
         // 非便携模式下，若 config.toml 不存在，则将模板配置复制到用户配置目录
-        let template_path = app
-            .path()
-            .resolve("resources/config-template.toml", BaseDirectory::Resource)
-            .map_err(|e| format!("failed to resolve resources/config-template.toml: {e}"))?;
-        logics::config::copy_template_config(template_path, config_path)?;
+        copy_template_config(&app, config_path)?;
     }
-    return logics::config::read_config(config_path);
+    let config = logics::config::read_config(config_path)?;
+    // StarDict 词典加载失败不影响读取配置
+    if let Err(e) = stardict.select(config_path, config.stardict_bundle()) {
+        println!("failed to load StarDict dictionary: {e}");
+    }
+    return Ok(config);
+}
+
+fn copy_template_config(app: &AppHandle, config_path: &Path) -> Result<()> {
+    let template_path = app
+        .path()
+        .resolve("resources/config-template.toml", BaseDirectory::Resource)
+        .map_err(|e| {
+            Error::internal("failed to resolve resources/config-template.toml").with_detail(e)
+        })?;
+    return logics::config::copy_template_config(template_path, config_path);
+}
+
+/// 用配置模板重新创建配置文件，用于配置文件丢失（`ConfigMissing`）或损坏时恢复
+#[tauri::command(rename_all = "snake_case")]
+pub fn recreate_config(
+    config_path: State<ConfigPath>,
+    stardict: State<SelectedStarDict>,
+    app: AppHandle,
+) -> Result<Config> {
+    let config_path: &Path = config_path.0.as_ref();
+    copy_template_config(&app, config_path)?;
+    let config = logics::config::read_config(config_path)?;
+    stardict.select(config_path, config.stardict_bundle())?;
+    return Ok(config);
 }
 
+/// 修改配置文件，`revision` 为界面读取配置时的文件标识，返回写入后的文件标识
 #[tauri::command(rename_all = "snake_case")]
 pub fn commit_config(
     modified: PartialConfig,
+    revision: String,
     config_path: State<ConfigPath>,
-) -> Result<(), String> {
+    stardict: State<SelectedStarDict>,
+) -> Result<String> {
     let config_path: &Path = config_path.0.as_ref();
-    return logics::config::commit_config(config_path, modified);
+    // 先打开新选择的 StarDict 词典，打开失败时不修改配置文件；写入配置文件成功后才替换正在使用的词典
+    let choice = match modified.stardict_bundle() {
+        Some(bundle) => Some(stardict.prepare(config_path, bundle)?),
+        None => None,
+    };
+    let revision = logics::config::commit_config(config_path, modified, &revision)?;
+    if let Some(choice) = choice {
+        stardict.apply(choice);
+    }
+    return Ok(revision);
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_profiles(config_path: State<ConfigPath>) -> Result<Vec<String>> {
+    return logics::config::list_profiles(&config_path.0);
+}
+
+/// 创建配置方案，`copy_from` 为空时各配置项使用默认值，否则复制该方案的配置项
+#[tauri::command(rename_all = "snake_case")]
+pub fn create_profile(
+    name: String,
+    copy_from: Option<String>,
+    revision: String,
+    config_path: State<ConfigPath>,
+) -> Result<Config> {
+    logics::config::create_profile(&config_path.0, &name, copy_from.as_deref(), &revision)?;
+    return logics::config::read_config(&config_path.0);
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn rename_profile(
+    old_name: String,
+    new_name: String,
+    revision: String,
+    config_path: State<ConfigPath>,
+) -> Result<Config> {
+    logics::config::rename_profile(&config_path.0, &old_name, &new_name, &revision)?;
+    return logics::config::read_config(&config_path.0);
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn delete_profile(
+    name: String,
+    revision: String,
+    config_path: State<ConfigPath>,
+) -> Result<Config> {
+    logics::config::delete_profile(&config_path.0, &name, &revision)?;
+    return logics::config::read_config(&config_path.0);
+}
+
+/// 切换当前使用的配置方案，返回新方案的配置
+#[tauri::command(rename_all = "snake_case")]
+pub fn switch_profile(
+    name: String,
+    revision: String,
+    config_path: State<ConfigPath>,
+) -> Result<Config> {
+    logics::config::switch_profile(&config_path.0, &name, &revision)?;
+    return logics::config::read_config(&config_path.0);
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -87,46 +182,47 @@ pub fn is_portable(portable: State<Portable>) -> bool {
 }
 
 #[tauri::command(rename_all = "snake_case")]
-pub fn show_in_explorer(path: String) -> Result<(), String> {
+pub fn show_in_explorer(path: String) -> Result<()> {
     return logics::utils::show_in_explorer(&path);
 }
 
 #[tauri::command(rename_all = "snake_case")]
-pub fn open_filepath(path: String) -> Result<(), String> {
+pub fn open_filepath(path: String) -> Result<()> {
     return logics::utils::open_filepath(&path);
 }
 
 #[tauri::command(rename_all = "snake_case")]
-pub fn open_in_browser(url: String) -> Result<(), String> {
+pub fn open_in_browser(url: String) -> Result<()> {
     return logics::utils::open_in_browser(&url);
 }
 
-pub struct IsWatching(pub Mutex<bool>);
+/// 正在运行的配置文件监视器，未启动或已停止时为 `None`
+pub struct ConfigWatcher(pub Mutex<Option<FileWatcher>>);
 
-impl IsWatching {
+impl ConfigWatcher {
     pub fn new() -> Self {
-        return IsWatching(Mutex::new(false));
+        return ConfigWatcher(Mutex::new(None));
     }
 }
 
 /// Return true if the watcher is started successfully, false if it's already started.
 #[tauri::command(rename_all = "snake_case")]
 pub fn start_config_watcher(
-    is_watching: State<IsWatching>,
+    config_watcher: State<ConfigWatcher>,
     config_path: State<ConfigPath>,
     app: AppHandle,
-) -> Result<bool, String> {
-    let watching = *is_watching
+) -> Result<bool> {
+    let mut watcher = config_watcher
         .0
         .lock()
-        .map_err(|e| format!("failed to lock is_watching: {e}"))?;
-    if watching {
+        .map_err(|e| Error::internal("failed to lock config watcher").with_detail(e))?;
+    if watcher.is_some() {
         return Ok(false);
     }
     let config_path: &Path = config_path.0.as_ref();
     let window = app
         .get_webview_window("main")
-        .ok_or("failed to get main window")?;
+        .ok_or_else(|| Error::internal("failed to get main window"))?;
     let main_window = window.clone();
     let on_change = move || {
         if main_window.emit("config-changed", ()).is_err() {
@@ -134,22 +230,39 @@ pub fn start_config_watcher(
         }
     };
     let main_window = window.clone();
-    let on_error = move || {
-        if main_window.emit("config-watcher-error", ()).is_err() {
+    let on_error = move |e: Error| {
+        println!("config watcher error: {e}");
+        if main_window.emit("config-watcher-error", e).is_err() {
             println!("failed to emit config-watcher-error event");
         }
     };
     let timeout = Duration::from_secs(2);
-    logics::utils::watch_file_change(config_path, on_change, on_error, timeout)?;
-    let mut guard = is_watching
+    *watcher = Some(logics::utils::watch_file_change(
+        config_path,
+        on_change,
+        on_error,
+        timeout,
+    )?);
+    return Ok(true);
+}
+
+/// 停止配置文件监视器，之后可以再次启动。
+/// Return true if the watcher is stopped, false if it's not running.
+#[tauri::command(rename_all = "snake_case")]
+pub fn stop_config_watcher(config_watcher: State<ConfigWatcher>) -> Result<bool> {
+    let watcher = config_watcher
         .0
         .lock()
-        .map_err(|e| format!("failed to lock is_watching: {e}"))?;
-    *guard = true;
+        .map_err(|e| Error::internal("failed to lock config watcher").with_detail(e))?
+        .take();
+    let Some(watcher) = watcher else {
+        return Ok(false);
+    };
+    watcher.stop();
     return Ok(true);
 }
 
 #[tauri::command(rename_all = "snake_case")]
-pub fn rust_in_release() -> Result<bool, String> {
+pub fn rust_in_release() -> Result<bool> {
     return Ok(!cfg!(debug_assertions));
 }
@@ -1,13 +1,28 @@
-use std::ops::DerefMut;
 use std::path::Path;
-use std::sync::{Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, RwLock};
+use std::time::Duration;
 
 use rusqlite::Connection;
 use tauri::path::BaseDirectory;
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 use super::logics;
-use super::logics::dict::{CollinsItem, OxfordItem};
+use super::logics::cache::{CacheStats, DictCache};
+use super::logics::dict::Dictionary;
+use super::logics::dict::{
+    AutocompleteItem, CollinsItem, DictionaryEntry, DictionaryInfo, OxfordItem, TokenLookup,
+    WordForm,
+};
+use super::logics::error::{Error, ErrorKind, Result};
+use super::logics::frequency::{FrequencySource, WordLevel};
+use super::logics::fts::DefinitionHit;
+use super::logics::morphology::BaseCandidate;
+use super::logics::pool::ConnectionPool;
+use super::logics::registry::DictionaryRegistry;
+use super::logics::stardict::StarDict;
+use super::logics::word_index::{Suggestion, WordIndex};
+use super::user;
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct DictPath(pub String);
@@ -16,7 +31,7 @@ impl DictPath {
     pub fn new(
         portable: bool,
         path_resolver: &tauri::path::PathResolver<impl tauri::Runtime>,
-    ) -> Result<Self, String> {
+    ) -> Result<Self> {
         let dict_path = if portable {
             logics::utils::current_exe_dir()?
                 .join("resources")
@@ -24,74 +39,658 @@ impl DictPath {
         } else {
             path_resolver
                 .resolve("resources/dict.db", BaseDirectory::Resource)
-                .map_err(|e| format!("failed to resolve resources/dict.db: {e}"))?
+                .map_err(|e| {
+                    Error::internal("failed to resolve resources/dict.db").with_detail(e)
+                })?
         };
         return Ok(DictPath(dict_path.to_string_lossy().into_owned()));
     }
 }
 
-trait GetConnection<'a> {
-    fn connection(&'a mut self, dict_path: &Path) -> Result<&'a mut Connection, String>;
+/// 额外词典所在的目录，位于 dict.db 旁边的 `dictionaries/`
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct DictionariesPath(pub String);
+
+impl DictionariesPath {
+    pub fn new(dict_path: &DictPath) -> Result<Self> {
+        let dict_path: &Path = dict_path.0.as_ref();
+        let dir = dict_path
+            .parent()
+            .ok_or_else(|| {
+                Error::internal("dict path is a root or an empty string").with_path(dict_path)
+            })?
+            .join("dictionaries");
+        return Ok(DictionariesPath(dir.to_string_lossy().into_owned()));
+    }
+}
+
+/// 检查 dict.db 是否可用，不可用时给出具体原因（如 dict.db 只是未下载的 Git LFS 指针文件）
+#[tauri::command(rename_all = "snake_case")]
+pub fn check_dict_db(dict_path: State<DictPath>) -> Result<()> {
+    return logics::dict::validate_dict_db(&dict_path.0);
+}
+
+/// 从 `dictionaries/` 中加载的词典，启动时为空，由 `spawn_dictionaries_loader` 在后台加载
+pub struct Dictionaries(pub RwLock<DictionaryRegistry>);
+
+impl Dictionaries {
+    pub fn new() -> Self {
+        return Dictionaries(RwLock::new(DictionaryRegistry::default()));
+    }
+}
+
+/// 在后台线程中加载 `dictionaries/` 中的词典，避免大的 .mdx 文件拖慢窗口的显示
+///
+/// 加载完成后向主窗口发送 `dictionaries-loaded` 事件，事件内容为加载失败的原因。
+pub fn spawn_dictionaries_loader(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let dictionaries_path = app.state::<DictionariesPath>();
+        let (registry, errors) = DictionaryRegistry::load(&dictionaries_path.0);
+        for e in &errors {
+            println!("failed to load dictionary: {e}");
+        }
+        {
+            let dictionaries = app.state::<Dictionaries>();
+            let mut guard = dictionaries
+                .0
+                .write()
+                .unwrap_or_else(PoisonError::into_inner);
+            *guard = registry;
+        }
+        let Some(window) = app.get_webview_window("main") else {
+            println!("failed to get main window");
+            return;
+        };
+        if window.emit("dictionaries-loaded", errors).is_err() {
+            println!("failed to emit dictionaries loaded event");
+        }
+    });
+}
+
+/// 词频数据，来自 dict.db 中的 frequency 表或 dict.db 旁边的 frequency.db，都没有时为 `None`
+pub struct Frequency(pub RwLock<Option<FrequencySource>>);
+
+impl Frequency {
+    pub fn load(dict_path: &DictPath) -> Self {
+        let source = match FrequencySource::open(&dict_path.0) {
+            Ok(source) => source,
+            Err(e) => {
+                println!("failed to load word frequency data: {e}");
+                None
+            }
+        };
+        return Frequency(RwLock::new(source));
+    }
+
+    /// dict.db 被替换后重新加载词频数据
+    pub fn reload(&self, dict_path: &DictPath) {
+        let Frequency(source) = Frequency::load(dict_path);
+        let source = source.into_inner().unwrap_or_else(PoisonError::into_inner);
+        let mut guard = self.0.write().unwrap_or_else(PoisonError::into_inner);
+        *guard = source;
+    }
 }
 
-impl<'a> GetConnection<'a> for MutexGuard<'_, Option<Connection>> {
-    fn connection(&'a mut self, dict_path: &Path) -> Result<&'a mut Connection, String> {
-        let opt_conn = self.deref_mut();
-        let conn = match opt_conn {
-            Some(conn) => conn,
-            None => {
-                let conn = logics::dict::open_connection(dict_path)?;
-                *opt_conn = Some(conn);
-                opt_conn.as_mut().expect("unexpected None")
+/// 配置文件中选择的 StarDict 词典
+pub struct SelectedStarDict(pub RwLock<Option<StarDict>>);
+
+impl SelectedStarDict {
+    pub fn new() -> Self {
+        return SelectedStarDict(RwLock::new(None));
+    }
+
+    /// 按配置文件中的 `stardict-bundle` 加载 StarDict 词典，路径为空时取消选择
+    ///
+    /// 路径可以是 .ifo 文件，也可以是包含 .ifo 文件的目录。路径未改变时不会重新加载。
+    pub fn select(&self, config_path: &Path, bundle: &str) -> Result<()> {
+        let choice = self.prepare(config_path, bundle)?;
+        self.apply(choice);
+        return Ok(());
+    }
+
+    /// 按 `stardict-bundle` 打开 StarDict 词典，但不替换当前选择的词典，
+    /// 用于先确认词典可用、写入配置文件成功后再通过 `apply` 生效
+    pub fn prepare(&self, config_path: &Path, bundle: &str) -> Result<StarDictChoice> {
+        let ifo_path = if bundle.trim().is_empty() {
+            None
+        } else {
+            let path = logics::config::resolve_config_relative_path(config_path, bundle.trim());
+            Some(logics::stardict::find_ifo(&path)?)
+        };
+        {
+            let guard = self.0.read().unwrap_or_else(PoisonError::into_inner);
+            if guard.as_ref().map(|stardict| stardict.ifo_path()) == ifo_path.as_deref() {
+                return Ok(StarDictChoice::Unchanged);
             }
+        }
+        let stardict = match ifo_path {
+            Some(ifo_path) => Some(Box::new(StarDict::open(
+                logics::stardict::STARDICT_ID,
+                ifo_path,
+            )?)),
+            None => None,
+        };
+        return Ok(StarDictChoice::Replace(stardict));
+    }
+
+    pub fn apply(&self, choice: StarDictChoice) {
+        if let StarDictChoice::Replace(stardict) = choice {
+            let mut guard = self.0.write().unwrap_or_else(PoisonError::into_inner);
+            *guard = stardict.map(|stardict| *stardict);
+        }
+    }
+}
+
+/// 由 `SelectedStarDict::prepare` 打开、尚未生效的 StarDict 词典选择
+pub enum StarDictChoice {
+    /// 路径未改变，继续使用当前的词典
+    Unchanged,
+    /// 替换为新的词典，`None` 表示取消选择
+    Replace(Option<Box<StarDict>>),
+}
+
+/// 释义全文索引的缓存路径，仅当 dict.db 未自带全文索引时使用
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct DefinitionIndexPath(pub String);
+
+impl DefinitionIndexPath {
+    pub fn new(
+        portable: bool,
+        path_resolver: &tauri::path::PathResolver<impl tauri::Runtime>,
+    ) -> Result<Self> {
+        let cache_dir = if portable {
+            logics::utils::current_exe_dir()?.join("cache")
+        } else {
+            path_resolver.app_cache_dir().map_err(|e| {
+                Error::internal("failed to resolve app cache directory").with_detail(e)
+            })?
         };
-        return Ok(conn);
+        let index_path = cache_dir.join("definitions.db");
+        return Ok(DefinitionIndexPath(
+            index_path.to_string_lossy().into_owned(),
+        ));
+    }
+}
+
+/// 释义全文索引所在数据库的连接，第一次使用时在后台打开（必要时建立索引）
+///
+/// dict.db 被替换后换用新的 `DefinitionIndexCell`，基于旧文件打开的索引不再被使用。
+pub struct DefinitionIndex(Mutex<Arc<DefinitionIndexCell>>);
+
+#[derive(Default)]
+struct DefinitionIndexCell {
+    /// 是否已开始打开索引
+    started: AtomicBool,
+    index: OnceLock<Result<Mutex<Connection>>>,
+}
+
+impl DefinitionIndex {
+    pub fn new() -> Self {
+        return DefinitionIndex(Mutex::new(Arc::default()));
+    }
+
+    fn current(&self) -> Arc<DefinitionIndexCell> {
+        return self
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+    }
+
+    /// 丢弃已打开的索引，下次使用时重新打开
+    fn reset(&self) {
+        let mut guard = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        *guard = Arc::default();
     }
 }
 
+/// 在后台线程中打开释义索引（必要时建立索引）
+///
+/// 建立索引使用单独的 dict.db 连接，不占用连接池中的连接，也不阻塞其他释义查询。
+fn spawn_definition_index_builder(app: &AppHandle, cell: Arc<DefinitionIndexCell>) {
+    if cell.started.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let index_path = app.state::<DefinitionIndexPath>();
+        let dict_path = app.state::<DictPath>();
+        let index = cell.index.get_or_init(|| {
+            let dict_conn = logics::dict::open_connection(&dict_path.0)?;
+            let index_conn = logics::fts::open_index(&index_path.0, &dict_path.0, &dict_conn)?;
+            return Ok(Mutex::new(index_conn));
+        });
+        if let Err(e) = index {
+            println!("failed to build definition index: {e}");
+        }
+    });
+}
+
+/// dict.db 连接池的大小
+pub const CONNECTION_POOL_SIZE: usize = 4;
+
+/// collins、oxford 查询结果缓存中每本词典最多缓存的单词数
+pub const DICT_CACHE_CAPACITY: usize = 1024;
+
+/// 在阻塞线程池中执行查询，使查询既不阻塞主线程，也不阻塞异步运行时
+pub async fn run_blocking<T, F>(app: AppHandle, f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&AppHandle) -> Result<T> + Send + 'static,
+{
+    return tauri::async_runtime::spawn_blocking(move || f(&app))
+        .await
+        .map_err(|e| Error::internal("failed to run query in background").with_detail(e))?;
+}
+
 #[tauri::command(rename_all = "snake_case")]
-pub fn search_collins(
-    word: String,
-    conn: State<Mutex<Option<Connection>>>,
-    dict_path: State<DictPath>,
-) -> Result<Vec<CollinsItem>, String> {
-    let dict_path: &Path = dict_path.0.as_ref();
-    let mut guard = conn
-        .lock()
-        .map_err(|e| format!("failed to lock connection: {e}"))?;
-    let conn = guard.connection(dict_path)?;
-    return logics::dict::search_collins(conn, word);
+pub async fn search_collins(word: String, app: AppHandle) -> Result<Vec<CollinsItem>> {
+    return run_blocking(app, move |app| {
+        let entries = user::user_entries_for(app, &[&word]);
+        let pool = app.state::<ConnectionPool>();
+        let conn = pool.get()?;
+        let cache = app.state::<DictCache>();
+        let items = cache.search_collins(&conn, conn.generation(), &word)?;
+        return Ok(logics::dict::merge_user_collins(items, &entries));
+    })
+    .await;
 }
 
 #[tauri::command(rename_all = "snake_case")]
-pub fn search_oxford(
+pub async fn search_oxford(word: String, app: AppHandle) -> Result<Vec<OxfordItem>> {
+    return run_blocking(app, move |app| {
+        let entries = user::user_entries_for(app, &[&word]);
+        let pool = app.state::<ConnectionPool>();
+        let conn = pool.get()?;
+        let cache = app.state::<DictCache>();
+        let items = cache.search_oxford(&conn, conn.generation(), &word)?;
+        return Ok(logics::dict::merge_user_oxford(items, &entries));
+    })
+    .await;
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_word_base(word: String, app: AppHandle) -> Result<Option<String>> {
+    return run_blocking(app, move |app| {
+        let pool = app.state::<ConnectionPool>();
+        let conn = pool.get()?;
+        return logics::dict::resolve_word_base(&conn, word);
+    })
+    .await;
+}
+
+/// 获取原型的所有词形（复数、过去式、过去分词、-ing 形式、比较级等）
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_word_forms(base: String, app: AppHandle) -> Result<Vec<WordForm>> {
+    return run_blocking(app, move |app| {
+        let pool = app.state::<ConnectionPool>();
+        let conn = pool.get()?;
+        return logics::dict::get_word_forms(&conn, base);
+    })
+    .await;
+}
+
+/// 查询结果缓存的统计信息（缓存的单词数、命中与未命中次数、因 dict.db 被替换而失效的次数）
+#[tauri::command(rename_all = "snake_case")]
+pub fn dict_cache_stats(cache: State<DictCache>) -> Result<CacheStats> {
+    return Ok(cache.stats());
+}
+
+/// 按构词规则推测单词的所有可能原型，并给出推导所用的规则
+#[tauri::command(rename_all = "snake_case")]
+pub async fn guess_word_bases(word: String, app: AppHandle) -> Result<Vec<BaseCandidate>> {
+    return run_blocking(app, move |app| {
+        let pool = app.state::<ConnectionPool>();
+        let conn = pool.get()?;
+        return logics::dict::guess_word_bases(&conn, word);
+    })
+    .await;
+}
+
+/// 一次性查询句子中所有词的原型和词典词条，避免逐词多次调用
+#[tauri::command(rename_all = "snake_case")]
+pub async fn lookup_sentence(tokens: Vec<String>, app: AppHandle) -> Result<Vec<TokenLookup>> {
+    return run_blocking(app, move |app| {
+        let pool = app.state::<ConnectionPool>();
+        let conn = pool.get()?;
+        let cache = app.state::<DictCache>();
+        let frequency = app.state::<Frequency>();
+        let frequency = frequency.0.read().unwrap_or_else(PoisonError::into_inner);
+        let mut lookups = logics::dict::lookup_tokens(
+            &conn,
+            conn.generation(),
+            &cache,
+            frequency.as_ref(),
+            &tokens,
+        )?;
+        drop(frequency);
+        drop(conn);
+        for lookup in &mut lookups {
+            let entries = user::user_entries_for(app, &lookup.words());
+            lookup.merge_user_entries(&entries);
+        }
+        return Ok(lookups);
+    })
+    .await;
+}
+
+/// 批量查询单词的词频与难度（词频排名、CEFR 等级、考试词表标签），单词不在词频表中时使用其原型
+///
+/// 没有词频数据或查不到单词时，对应的结果为 `null`。
+#[tauri::command(rename_all = "snake_case")]
+pub async fn word_levels(words: Vec<String>, app: AppHandle) -> Result<Vec<Option<WordLevel>>> {
+    return run_blocking(app, move |app| {
+        let frequency = app.state::<Frequency>();
+        let frequency = frequency.0.read().unwrap_or_else(PoisonError::into_inner);
+        let Some(frequency) = frequency.as_ref() else {
+            return Ok(vec![None; words.len()]);
+        };
+        let pool = app.state::<ConnectionPool>();
+        let conn = pool.get()?;
+        let mut levels = Vec::with_capacity(words.len());
+        for word in &words {
+            let base = logics::dict::resolve_word_base(&conn, word)?;
+            levels.push(logics::frequency::word_level(
+                frequency,
+                word,
+                base.as_deref(),
+            )?);
+        }
+        return Ok(levels);
+    })
+    .await;
+}
+
+/// 按释义反查单词，支持中文（匹配 cnDef）和英文（匹配 enDef）
+///
+/// dict.db 未自带全文索引时，第一次查询会在后台建立索引，索引可用之前返回 `NotReady` 错误。
+#[tauri::command(rename_all = "snake_case")]
+pub async fn search_definitions(
+    query: String,
+    limit: Option<usize>,
+    app: AppHandle,
+) -> Result<Vec<DefinitionHit>> {
+    return run_blocking(app, move |app| {
+        let limit = limit.unwrap_or(50);
+        let pool = app.state::<ConnectionPool>();
+        let conn = pool.get()?;
+        if logics::fts::has_builtin_index(&conn)? {
+            return logics::fts::search_definitions(&conn, &conn, query, limit);
+        }
+        let cell = app.state::<DefinitionIndex>().current();
+        let Some(index) = cell.index.get() else {
+            spawn_definition_index_builder(app, cell.clone());
+            return Err(Error::new(
+                ErrorKind::NotReady,
+                "definition index is being built, try again later",
+            ));
+        };
+        let index_conn = index.as_ref().map_err(Clone::clone)?;
+        let index_conn = index_conn.lock().unwrap_or_else(PoisonError::into_inner);
+        return logics::fts::search_definitions(&index_conn, &conn, query, limit);
+    })
+    .await;
+}
+
+/// 返回以 `prefix` 开头的词头，用于输入时的自动补全
+#[tauri::command(rename_all = "snake_case")]
+pub async fn autocomplete(
+    prefix: String,
+    limit: Option<usize>,
+    app: AppHandle,
+) -> Result<Vec<AutocompleteItem>> {
+    return run_blocking(app, move |app| {
+        let pool = app.state::<ConnectionPool>();
+        let conn = pool.get()?;
+        return logics::dict::autocomplete(&conn, prefix, limit.unwrap_or(20));
+    })
+    .await;
+}
+
+/// 拼写建议所用的词头索引，每个 dict.db 只构建一次
+///
+/// dict.db 被替换后换用新的 `OnceLock`，正在使用旧索引的查询不受影响。
+pub struct SuggestionIndex(Mutex<Arc<OnceLock<Result<Arc<WordIndex>>>>>);
+
+impl SuggestionIndex {
+    pub fn new() -> Self {
+        return SuggestionIndex(Mutex::new(Arc::new(OnceLock::new())));
+    }
+
+    /// 获取索引，若索引尚未构建则在当前线程构建（若其他线程正在构建，则等待其完成）
+    fn get(&self, pool: &ConnectionPool) -> Result<Arc<WordIndex>> {
+        let cell = self
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let index = cell.get_or_init(|| {
+            let conn = pool.get()?;
+            return WordIndex::build(&conn).map(Arc::new);
+        });
+        return index.clone();
+    }
+
+    /// 丢弃已构建的索引，下次使用时重新构建
+    fn reset(&self) {
+        let mut guard = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        *guard = Arc::new(OnceLock::new());
+    }
+}
+
+/// 在后台线程中构建拼写建议索引，使第一次查询时无需等待
+pub fn spawn_suggestion_index_builder(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let pool = app.state::<ConnectionPool>();
+        let index = app.state::<SuggestionIndex>();
+        if let Err(e) = index.get(&pool) {
+            println!("failed to build suggestion index: {e}");
+        }
+    });
+}
+
+/// 返回与 `word` 拼写最接近的词头，用于查不到单词时给出建议
+#[tauri::command(rename_all = "snake_case")]
+pub async fn suggest_words(
     word: String,
-    conn: State<Mutex<Option<Connection>>>,
-    dict_path: State<DictPath>,
-) -> Result<Vec<OxfordItem>, String> {
-    let dict_path: &Path = dict_path.0.as_ref();
-    let mut guard = conn
-        .lock()
-        .map_err(|e| format!("failed to lock connection: {e}"))?;
-    let conn = guard.connection(dict_path)?;
-    return logics::dict::search_oxford(conn, word);
+    limit: Option<usize>,
+    app: AppHandle,
+) -> Result<Vec<Suggestion>> {
+    return run_blocking(app, move |app| {
+        let pool = app.state::<ConnectionPool>();
+        let index = app.state::<SuggestionIndex>();
+        let index = index.get(&pool)?;
+        return Ok(index.suggest(&word, limit.unwrap_or(10)));
+    })
+    .await;
 }
 
+/// 列出所有可用的词典，依次为自带的词典、配置文件中选择的 StarDict 词典、`dictionaries/` 中的词典
 #[tauri::command(rename_all = "snake_case")]
-pub fn get_word_base(
+pub fn list_dictionaries(
+    dictionaries: State<Dictionaries>,
+    stardict: State<SelectedStarDict>,
+) -> Result<Vec<DictionaryInfo>> {
+    let mut infos = logics::registry::builtin_dictionaries();
+    {
+        let stardict = stardict.0.read().unwrap_or_else(PoisonError::into_inner);
+        if let Some(stardict) = stardict.as_ref() {
+            infos.push(stardict.info().clone());
+        }
+    }
+    let registry = dictionaries
+        .0
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    infos.extend(registry.infos());
+    return Ok(infos);
+}
+
+/// 在指定的词典中查询单词，自带的词典也会转换为通用的词条格式
+#[tauri::command(rename_all = "snake_case")]
+pub async fn search_dictionary(
+    id: String,
     word: String,
-    conn: State<Mutex<Option<Connection>>>,
-    dict_path: State<DictPath>,
-) -> Result<Option<String>, String> {
-    let dict_path: &Path = dict_path.0.as_ref();
-    let mut guard = conn
-        .lock()
-        .map_err(|e| format!("failed to lock connection: {e}"))?;
-    let conn = guard.connection(dict_path)?;
-    return logics::dict::get_word_base(conn, word);
+    app: AppHandle,
+) -> Result<Vec<DictionaryEntry>> {
+    return run_blocking(app, move |app| {
+        if logics::registry::BUILTIN_DICTIONARY_IDS.contains(&id.as_str()) {
+            let user_entries = user::user_entries_for(app, &[&word]);
+            let pool = app.state::<ConnectionPool>();
+            let conn = pool.get()?;
+            let cache = app.state::<DictCache>();
+            let entries = if id == "collins" {
+                let items = cache.search_collins(&conn, conn.generation(), &word)?;
+                logics::dict::merge_user_collins(items, &user_entries)
+                    .into_iter()
+                    .map(DictionaryEntry::from)
+                    .collect()
+            } else {
+                let items = cache.search_oxford(&conn, conn.generation(), &word)?;
+                logics::dict::merge_user_oxford(items, &user_entries)
+                    .into_iter()
+                    .map(DictionaryEntry::from)
+                    .collect()
+            };
+            return Ok(entries);
+        }
+        if id == logics::stardict::STARDICT_ID {
+            let stardict = app.state::<SelectedStarDict>();
+            let stardict = stardict.0.read().unwrap_or_else(PoisonError::into_inner);
+            let stardict = stardict.as_ref().ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    "no StarDict dictionary is selected in config file",
+                )
+            })?;
+            return stardict.search(&word);
+        }
+        let dictionaries = app.state::<Dictionaries>();
+        let registry = dictionaries
+            .0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let dictionary = registry.get(&id).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("dictionary {id} does not exist"),
+            )
+        })?;
+        return dictionary.search(&word);
+    })
+    .await;
+}
+
+/// 获取词典中引用的资源（如 MDict 词典 .mdd 文件中的图片、音频），以二进制形式返回
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_dictionary_resource(
+    id: String,
+    path: String,
+    app: AppHandle,
+) -> Result<tauri::ipc::Response> {
+    return run_blocking(app, move |app| {
+        let dictionaries = app.state::<Dictionaries>();
+        let registry = dictionaries
+            .0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let dictionary = registry.get(&id).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("dictionary {id} does not exist"),
+            )
+        })?;
+        let data = dictionary.resource(&path)?.ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("resource {path} does not exist in dictionary {id}"),
+            )
+        })?;
+        return Ok(tauri::ipc::Response::new(data));
+    })
+    .await;
+}
+
+/// 重新加载 `dictionaries/` 中的词典，返回加载失败的原因
+#[tauri::command(rename_all = "snake_case")]
+pub async fn reload_dictionaries(app: AppHandle) -> Result<Vec<Error>> {
+    return run_blocking(app, move |app| {
+        let dictionaries_path = app.state::<DictionariesPath>();
+        let (registry, errors) = DictionaryRegistry::load(&dictionaries_path.0);
+        let dictionaries = app.state::<Dictionaries>();
+        let mut guard = dictionaries
+            .0
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        *guard = registry;
+        return Ok(errors);
+    })
+    .await;
+}
+
+/// dict.db 被替换后切换到新文件
+///
+/// 新文件检查通过后，切换连接池中的连接，并丢弃基于旧文件的查询缓存、词频数据、释义全文索引连接和拼写建议索引。
+fn reload_dict_db(app: &AppHandle) -> Result<()> {
+    let pool = app.state::<ConnectionPool>();
+    let generation = pool.reload()?;
+    app.state::<DictCache>().invalidate(generation);
+    app.state::<Frequency>().reload(&app.state::<DictPath>());
+    app.state::<DefinitionIndex>().reset();
+    app.state::<SuggestionIndex>().reset();
+    spawn_suggestion_index_builder(app);
+    return Ok(());
+}
+
+/// dict.db 的监视器，由应用状态持有，应用退出时停止
+struct DictWatcher {
+    _watcher: logics::utils::FileWatcher,
+}
+
+/// 监视 dict.db，文件被替换时重新加载词典
+///
+/// 重新加载成功时向主窗口发送 `dict-changed` 事件；新文件不可用时继续使用旧的连接，
+/// 并发送 `dict-invalid` 事件，事件内容为不可用的原因。
+pub fn start_dict_watcher(app: &AppHandle) -> Result<()> {
+    let dict_path = app.state::<DictPath>();
+    let reload_app = app.clone();
+    let on_change = move || {
+        let result = reload_dict_db(&reload_app);
+        let Some(window) = reload_app.get_webview_window("main") else {
+            println!("failed to get main window");
+            return;
+        };
+        let emitted = match result {
+            Ok(()) => window.emit("dict-changed", ()),
+            Err(e) => {
+                println!("failed to reload dict.db: {e}");
+                window.emit("dict-invalid", e)
+            }
+        };
+        if emitted.is_err() {
+            println!("failed to emit dict.db reload event");
+        }
+    };
+    let error_app = app.clone();
+    let on_error = move |e: Error| {
+        println!("dict.db watcher error: {e}");
+        let Some(window) = error_app.get_webview_window("main") else {
+            println!("failed to get main window");
+            return;
+        };
+        if window.emit("dict-watcher-error", e).is_err() {
+            println!("failed to emit dict-watcher-error event");
+        }
+    };
+    let timeout = Duration::from_secs(2);
+    let watcher = logics::utils::watch_file_change(&dict_path.0, on_change, on_error, timeout)?;
+    app.manage(DictWatcher { _watcher: watcher });
+    return Ok(());
 }
 
 #[tauri::command(rename_all = "snake_case")]
-pub fn sanitize_filename(filename: String) -> Result<String, String> {
+pub fn sanitize_filename(filename: String) -> Result<String> {
     return Ok(sanitise_file_name::sanitise(&filename));
 }
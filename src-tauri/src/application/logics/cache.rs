@@ -0,0 +1,248 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+use rusqlite::Connection;
+
+use super::dict::{self, CollinsItem, OxfordItem};
+use super::error::Result;
+
+/// 容量有限的 LRU 缓存，键统一为小写（与词典查询不区分大小写一致）
+pub struct LruCache<V> {
+    capacity: usize,
+    /// 键到值及其最近使用时刻的映射
+    entries: HashMap<String, (V, u64)>,
+    /// 最近使用时刻到键的映射，最小的时刻即为最久未使用的项
+    order: BTreeMap<u64, String>,
+    /// 单调递增的使用时刻
+    tick: u64,
+}
+
+impl<V: Clone> LruCache<V> {
+    pub fn new(capacity: usize) -> Self {
+        return LruCache {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+        };
+    }
+
+    pub fn get(&mut self, key: &str) -> Option<V> {
+        let key = key.to_lowercase();
+        self.tick += 1;
+        let (value, last_used) = self.entries.get_mut(&key)?;
+        self.order.remove(last_used);
+        *last_used = self.tick;
+        self.order.insert(self.tick, key);
+        return Some(value.clone());
+    }
+
+    pub fn insert(&mut self, key: &str, value: V) {
+        let key = key.to_lowercase();
+        self.tick += 1;
+        if let Some((_, last_used)) = self.entries.insert(key.clone(), (value, self.tick)) {
+            self.order.remove(&last_used);
+        }
+        self.order.insert(self.tick, key);
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+/// 词典缓存的统计信息
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    /// 每本词典最多缓存的单词数
+    capacity: usize,
+    collins_entries: usize,
+    oxford_entries: usize,
+    hits: u64,
+    misses: u64,
+    /// 因 dict.db 被替换而清空缓存的次数
+    invalidations: u64,
+}
+
+struct DictCacheState {
+    /// 缓存内容对应的 dict.db 标识，dict.db 被替换后标识改变，缓存随之清空
+    fingerprint: Option<String>,
+    /// 缓存内容对应的连接池 generation，基于更早的连接查询到的结果不会被缓存
+    generation: u64,
+    collins: LruCache<Vec<CollinsItem>>,
+    oxford: LruCache<Vec<OxfordItem>>,
+    hits: u64,
+    misses: u64,
+    invalidations: u64,
+}
+
+/// collins 和 oxford 查询结果的缓存
+///
+/// 每次查询前检查 dict.db 的大小和修改时间，文件被替换时自动清空缓存。
+/// 查询时需传入取出连接时连接池的 generation：连接池切换到新文件后，旧连接的查询结果既不读取也不写入缓存。
+pub struct DictCache {
+    dict_path: PathBuf,
+    capacity: usize,
+    state: Mutex<DictCacheState>,
+}
+
+impl DictCache {
+    pub fn new(dict_path: impl Into<PathBuf>, capacity: usize) -> Self {
+        return DictCache {
+            dict_path: dict_path.into(),
+            capacity,
+            state: Mutex::new(DictCacheState {
+                fingerprint: None,
+                generation: 0,
+                collins: LruCache::new(capacity),
+                oxford: LruCache::new(capacity),
+                hits: 0,
+                misses: 0,
+                invalidations: 0,
+            }),
+        };
+    }
+
+    pub fn search_collins(
+        &self,
+        conn: &Connection,
+        generation: u64,
+        word: &str,
+    ) -> Result<Vec<CollinsItem>> {
+        let key = match self.lookup(generation, |state| state.collins.get(word)) {
+            Lookup::Hit(items) => return Ok(items),
+            Lookup::Miss(key) => Some(key),
+            Lookup::Bypass => None,
+        };
+        let items = dict::search_collins(conn, word)?;
+        if let Some(key) = key {
+            self.store(&key, |state| state.collins.insert(word, items.clone()));
+        }
+        return Ok(items);
+    }
+
+    pub fn search_oxford(
+        &self,
+        conn: &Connection,
+        generation: u64,
+        word: &str,
+    ) -> Result<Vec<OxfordItem>> {
+        let key = match self.lookup(generation, |state| state.oxford.get(word)) {
+            Lookup::Hit(items) => return Ok(items),
+            Lookup::Miss(key) => Some(key),
+            Lookup::Bypass => None,
+        };
+        let items = dict::search_oxford(conn, word)?;
+        if let Some(key) = key {
+            self.store(&key, |state| state.oxford.insert(word, items.clone()));
+        }
+        return Ok(items);
+    }
+
+    /// 连接池切换到 `generation` 后清空缓存，包括 dict.db 被替换但大小和修改时间未变的情况
+    ///
+    /// 之后基于旧连接的查询结果不会再写入缓存。
+    pub fn invalidate(&self, generation: u64) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if generation > state.generation {
+            state.generation = generation;
+            state.reset();
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        return CacheStats {
+            capacity: self.capacity,
+            collins_entries: state.collins.len(),
+            oxford_entries: state.oxford.len(),
+            hits: state.hits,
+            misses: state.misses,
+            invalidations: state.invalidations,
+        };
+    }
+
+    fn lookup<V>(
+        &self,
+        generation: u64,
+        get: impl FnOnce(&mut DictCacheState) -> Option<V>,
+    ) -> Lookup<V> {
+        // dict.db 正在被替换时可能暂时无法读取文件信息，此时不使用缓存，直接查询
+        let Ok(fingerprint) = dict::dict_fingerprint(&self.dict_path) else {
+            return Lookup::Bypass;
+        };
+        // 缓存的状态只在锁内做简单的修改，其他线程 panic 后的状态仍然有效，可以直接恢复
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if generation < state.generation {
+            return Lookup::Bypass;
+        }
+        if generation > state.generation {
+            state.generation = generation;
+            state.reset();
+        }
+        if state.fingerprint.as_deref() != Some(fingerprint.as_str()) {
+            state.reset();
+            state.fingerprint = Some(fingerprint.clone());
+        }
+        let value = get(&mut state);
+        return match value {
+            Some(value) => {
+                state.hits += 1;
+                Lookup::Hit(value)
+            }
+            None => {
+                state.misses += 1;
+                Lookup::Miss(CacheKey {
+                    fingerprint,
+                    generation,
+                })
+            }
+        };
+    }
+
+    /// 写入查询结果，缓存在查询期间被清空或切换到新文件时丢弃
+    fn store(&self, key: &CacheKey, insert: impl FnOnce(&mut DictCacheState)) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.generation == key.generation
+            && state.fingerprint.as_deref() == Some(key.fingerprint.as_str())
+        {
+            insert(&mut state);
+        }
+    }
+}
+
+impl DictCacheState {
+    fn reset(&mut self) {
+        if self.fingerprint.take().is_some() {
+            self.invalidations += 1;
+        }
+        self.collins.clear();
+        self.oxford.clear();
+    }
+}
+
+/// 未命中缓存时查询所基于的 dict.db 标识和连接池 generation
+struct CacheKey {
+    fingerprint: String,
+    generation: u64,
+}
+
+enum Lookup<V> {
+    Hit(V),
+    Miss(CacheKey),
+    /// 不使用缓存：无法读取 dict.db 的文件信息，或连接打开于 dict.db 被替换之前
+    Bypass,
+}
@@ -1,4 +1,8 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use super::config_migration;
+use super::error::{Error, ErrorKind, Issue, Result, TextSpan};
+use super::utils;
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -7,6 +11,16 @@ pub struct Config {
     anki_connect_url: String,
     deck_name: String,
     model_name: String,
+    /// StarDict 词典的 .ifo 文件路径，为空表示不使用
+    stardict_bundle: String,
+    /// 当前使用的配置方案名，以上除 `stardict_bundle` 外的配置项均来自此方案
+    active_profile: String,
+    /// 所有配置方案名，按在配置文件中的顺序排列
+    profiles: Vec<String>,
+    /// 配置文件内容的标识，修改配置文件时传回，用于检查文件是否已被其他程序修改
+    revision: String,
+    /// 不影响使用的问题，如拼错的键、已用默认值代替的无效值
+    warnings: Vec<Issue>,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -16,91 +30,730 @@ pub struct PartialConfig {
     anki_connect_url: Option<String>,
     deck_name: Option<String>,
     model_name: Option<String>,
+    stardict_bundle: Option<String>,
+}
+
+impl Config {
+    pub fn stardict_bundle(&self) -> &str {
+        return &self.stardict_bundle;
+    }
+}
+
+impl PartialConfig {
+    pub fn stardict_bundle(&self) -> Option<&str> {
+        return self.stardict_bundle.as_deref();
+    }
 }
 
 /// 将配置模板复制到配置文件路径
 pub fn copy_template_config(
     template_path: impl AsRef<Path>,
     config_path: impl AsRef<Path>,
-) -> Result<(), String> {
-    fn inner(config_path: &Path, template_path: &Path) -> Result<(), String> {
-        let config_dir = config_path
-            .parent()
-            .ok_or("config path is a root or an empty string")?;
+) -> Result<()> {
+    fn inner(config_path: &Path, template_path: &Path) -> Result<()> {
+        let config_dir = config_path.parent().ok_or_else(|| {
+            Error::internal("config path is a root or an empty string").with_path(config_path)
+        })?;
         std::fs::create_dir_all(config_dir)
-            .map_err(|e| format!("failed to create directory {}: {e}", config_dir.display()))?;
+            .map_err(|e| Error::io("failed to create config directory", e).with_path(config_dir))?;
         std::fs::copy(template_path, config_path).map_err(|e| {
-            format!(
-                "failed to copy template config from {} to {}: {e}",
-                template_path.display(),
-                config_path.display()
+            Error::io(
+                format!(
+                    "failed to copy template config from {}",
+                    template_path.display()
+                ),
+                e,
             )
+            .with_path(config_path)
         })?;
         return Ok(());
     }
     return inner(config_path.as_ref(), template_path.as_ref());
 }
 
-pub fn read_config(config_path: impl AsRef<Path>) -> Result<Config, String> {
-    fn inner(config_path: &Path) -> Result<Config, String> {
-        let toml_string = std::fs::read_to_string(config_path)
-            .map_err(|e| format!("failed to read config file {}: {e}", config_path.display()))?;
-        let doc = toml_string.parse::<toml_edit::DocumentMut>().map_err(|e| {
-            format!(
-                "failed to parse toml from config file {}: {e}",
-                config_path.display()
-            )
-        })?;
-        let anki_connect_url = doc
-            .get("anki-connect-url")
-            .ok_or(r#"toml key "anki-connect-url" does not exist"#)?
-            .as_str()
-            .ok_or(r#"the value of "anki-connect-url" is not a string"#)?;
-        let deck_name = doc
-            .get("deck-name")
-            .ok_or(r#"toml key "deck-name" does not exist"#)?
-            .as_str()
-            .ok_or(r#"the value of "deck-name" is not a string"#)?;
-        let model_name = doc
-            .get("model-name")
-            .ok_or(r#"toml key "model-name" does not exist"#)?
-            .as_str()
-            .ok_or(r#"the value of "model-name" is not a string"#)?;
+/// 配置项的取值类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueKind {
+    /// 字符串，`allow_empty` 为 `false` 时不能为空（或只有空白）
+    Text { allow_empty: bool },
+    /// http 或 https URL，端口（若有）须在 1 到 65535 之间
+    Url,
+}
+
+/// 一个配置项的类型、约束和默认值
+struct KeySchema {
+    key: &'static str,
+    kind: ValueKind,
+    /// 键不存在时使用的默认值，为 `None` 表示必须提供
+    default: Option<&'static str>,
+}
+
+/// 存放各个配置方案的表，每个方案为一个 `[profiles.<名称>]` 表
+const PROFILES_KEY: &str = "profiles";
+
+/// 记录当前使用的配置方案名的键
+const ACTIVE_PROFILE_KEY: &str = "active-profile";
+
+/// 默认的配置方案名
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// 每个配置方案中的配置项，默认值与 `config-template.toml` 一致
+const PROFILE_SCHEMA: &[KeySchema] = &[
+    KeySchema {
+        key: "anki-connect-url",
+        kind: ValueKind::Url,
+        default: Some("http://localhost:8765"),
+    },
+    KeySchema {
+        key: "deck-name",
+        kind: ValueKind::Text { allow_empty: false },
+        default: Some("划词助手默认牌组"),
+    },
+    KeySchema {
+        key: "model-name",
+        kind: ValueKind::Text { allow_empty: false },
+        default: Some("划词助手默认单词模板"),
+    },
+];
+
+/// 顶层的配置项，由所有配置方案共用
+const GLOBAL_SCHEMA: &[KeySchema] = &[
+    KeySchema {
+        key: "stardict-bundle",
+        kind: ValueKind::Text { allow_empty: true },
+        default: Some(""),
+    },
+    KeySchema {
+        key: ACTIVE_PROFILE_KEY,
+        kind: ValueKind::Text { allow_empty: false },
+        default: Some(DEFAULT_PROFILE),
+    },
+];
+
+/// 读取配置文件的内容，配置文件不存在时返回 `ConfigMissing` 错误
+fn read_text(config_path: &Path) -> Result<String> {
+    return std::fs::read_to_string(config_path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            return Error::new(ErrorKind::ConfigMissing, "config.toml does not exist")
+                .with_path(config_path);
+        }
+        return Error::io("failed to read config file", e).with_path(config_path);
+    });
+}
+
+/// 解析配置文件的内容，语法错误时给出出错的位置
+fn parse_text<T: std::str::FromStr<Err = toml_edit::TomlError>>(
+    text: &str,
+    config_path: &Path,
+) -> Result<T> {
+    return text.parse::<T>().map_err(|e| {
+        let issue = Issue {
+            key: None,
+            message: e.message().trim().to_string(),
+            span: e.span().map(|range| TextSpan::from_byte_range(text, range)),
+            hint: None,
+        };
+        Error::new(
+            ErrorKind::TomlParse,
+            "failed to parse toml from config file",
+        )
+        .with_path(config_path)
+        .with_issues(vec![issue])
+    });
+}
+
+/// 配置文件内容的标识（内容的 RIPEMD-128 摘要）
+fn revision_of(text: &str) -> String {
+    use ripemd::{Digest, Ripemd128};
+    return Ripemd128::digest(text.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+}
+
+/// 检查配置文件内容的标识是否与界面读取时的 `revision` 相同，不同时返回 `ConfigConflict` 错误
+fn check_text_revision(config_path: &Path, text: &str, revision: &str) -> Result<()> {
+    if revision_of(text) != revision {
+        return Err(Error::new(
+            ErrorKind::ConfigConflict,
+            "config file has been modified by another program since it was loaded",
+        )
+        .with_path(config_path));
+    }
+    return Ok(());
+}
+
+/// 读取并解析配置文件，用于修改配置文件（解析结果不保留各个值在文件中的位置）
+///
+/// 文件在读取为 `revision` 之后被修改过时返回 `ConfigConflict` 错误。
+fn read_document(config_path: &Path, revision: &str) -> Result<toml_edit::DocumentMut> {
+    let text = read_text(config_path)?;
+    check_text_revision(config_path, &text, revision)?;
+    return parse_text(&text, config_path);
+}
+
+/// 检查一个值是否符合配置项的约束，不符合时返回问题描述
+fn check_value(kind: ValueKind, value: &str) -> std::result::Result<(), String> {
+    match kind {
+        ValueKind::Text { allow_empty } => {
+            if !allow_empty && value.trim().is_empty() {
+                return Err("must not be empty".to_string());
+            }
+        }
+        ValueKind::Url => check_url(value)?,
+    }
+    return Ok(());
+}
+
+/// 检查 URL 是否为 `http(s)://host[:port][/path]` 的形式
+fn check_url(url: &str) -> std::result::Result<(), String> {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return Err(format!(r#""{url}" is not a URL"#));
+    };
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return Err(format!(r#"URL scheme "{scheme}" is not http or https"#));
+    }
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    // IPv6 地址写在方括号中，其中的冒号不是端口分隔符
+    let (host, port) = match authority.rfind(':') {
+        Some(i) if !authority[i..].contains(']') => (&authority[..i], Some(&authority[i + 1..])),
+        _ => (authority, None),
+    };
+    if host.is_empty() || host.chars().any(char::is_whitespace) {
+        return Err(format!(r#"URL "{url}" has no valid host"#));
+    }
+    if let Some(port) = port {
+        match port.parse::<u32>() {
+            Ok(1..=65535) => {}
+            _ => return Err(format!(r#"URL port "{port}" is not between 1 and 65535"#)),
+        }
+    }
+    return Ok(());
+}
+
+/// 去掉大小写、连字符和下划线的区别，用于识别拼错的键
+fn loose_key(key: &str) -> String {
+    return key
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect();
+}
+
+/// 检查配置文件时发现的问题
+#[derive(Default)]
+struct Findings {
+    /// 无法得到可用配置的问题，如缺少必需的键、当前配置方案不存在
+    errors: Vec<Issue>,
+    /// 不影响使用的问题，如拼错的键、已用默认值代替的无效值
+    warnings: Vec<Issue>,
+}
+
+/// 按 `schema` 检查表中的所有配置项，缺少的可选项和无效的值使用默认值
+///
+/// 返回各个键的值（与 `schema` 的顺序相同），发现的问题记录到 `findings` 中，问题中的键名带有前缀 `prefix`。
+fn validate_table(
+    table: &toml_edit::Table,
+    schema: &[KeySchema],
+    prefix: &str,
+    text: &str,
+    findings: &mut Findings,
+) -> Vec<String> {
+    let span_of = |span: Option<std::ops::Range<usize>>| {
+        return span.map(|range| TextSpan::from_byte_range(text, range));
+    };
+    let mut values = Vec::with_capacity(schema.len());
+    for key_schema in schema {
+        let default = key_schema.default.unwrap_or_default().to_string();
+        let Some(item) = table.get(key_schema.key) else {
+            if key_schema.default.is_none() {
+                findings.errors.push(Issue {
+                    key: Some(format!("{prefix}{}", key_schema.key)),
+                    message: "required key does not exist".to_string(),
+                    span: None,
+                    hint: None,
+                });
+            }
+            values.push(default);
+            continue;
+        };
+        let result = match item.as_str() {
+            Some(value) => check_value(key_schema.kind, value).map(|()| value.to_string()),
+            None => Err(format!("expected a string, found {}", item.type_name())),
+        };
+        match result {
+            Ok(value) => values.push(value),
+            Err(message) => {
+                let issue = Issue {
+                    key: Some(format!("{prefix}{}", key_schema.key)),
+                    message,
+                    span: span_of(item.span()),
+                    hint: key_schema
+                        .default
+                        .map(|default| format!(r#"default "{default}" is used instead"#)),
+                };
+                // 有默认值时用默认值代替无效的值，只给出警告
+                match key_schema.default {
+                    Some(_) => findings.warnings.push(issue),
+                    None => findings.errors.push(issue),
+                }
+                values.push(default);
+            }
+        }
+    }
+    // 其他的键不做检查，但与已知的键只有大小写、连字符或下划线不同的键很可能是拼错了
+    for (key, item) in table.iter() {
+        let Some(key_schema) = schema.iter().find(|key_schema| {
+            key_schema.key != key && loose_key(key_schema.key) == loose_key(key)
+        }) else {
+            continue;
+        };
+        let span = table
+            .key(key)
+            .and_then(|key| key.span())
+            .or_else(|| item.span());
+        findings.warnings.push(Issue {
+            key: Some(format!("{prefix}{key}")),
+            message: "unknown key".to_string(),
+            span: span_of(span),
+            hint: Some(format!(r#"did you mean "{}"?"#, key_schema.key)),
+        });
+    }
+    return values;
+}
+
+/// 配置文件中的各个配置方案，按在文件中的顺序排列
+///
+/// `profiles` 不存在、不是表或其中没有可用的方案时记录错误，某个方案不是表时忽略该方案并记录警告。
+fn profile_tables<'a>(
+    root: &'a toml_edit::Table,
+    text: &str,
+    findings: &mut Findings,
+) -> Vec<(&'a str, &'a toml_edit::Table)> {
+    let not_a_table = |key: String, item: &toml_edit::Item| {
+        return Issue {
+            key: Some(key),
+            message: format!("expected a table, found {}", item.type_name()),
+            span: item
+                .span()
+                .map(|range| TextSpan::from_byte_range(text, range)),
+            hint: Some(format!(
+                "write each profile as a [{PROFILES_KEY}.<name>] table"
+            )),
+        };
+    };
+    let no_profile = Issue {
+        key: Some(PROFILES_KEY.to_string()),
+        message: "no profile exists".to_string(),
+        span: None,
+        hint: Some(format!("add a [{PROFILES_KEY}.{DEFAULT_PROFILE}] table")),
+    };
+    let Some(item) = root.get(PROFILES_KEY) else {
+        findings.errors.push(no_profile);
+        return vec![];
+    };
+    let Some(table) = item.as_table() else {
+        findings
+            .errors
+            .push(not_a_table(PROFILES_KEY.to_string(), item));
+        return vec![];
+    };
+    let mut profiles = vec![];
+    for (name, item) in table.iter() {
+        match item.as_table() {
+            Some(profile) => profiles.push((name, profile)),
+            None => findings
+                .warnings
+                .push(not_a_table(format!("{PROFILES_KEY}.{name}"), item)),
+        }
+    }
+    if profiles.is_empty() {
+        findings.errors.push(no_profile);
+    }
+    return profiles;
+}
+
+/// 配置文件有错误时返回的错误，其中也包括所有警告
+fn invalid_config(config_path: &Path, findings: Findings) -> Error {
+    let Findings {
+        mut errors,
+        warnings,
+    } = findings;
+    let message = format!("config file has {} error(s)", errors.len());
+    errors.extend(warnings);
+    return Error::new(ErrorKind::InvalidValue, message)
+        .with_path(config_path)
+        .with_issues(errors);
+}
+
+/// 读取配置文件，必要时先升级旧版本的配置文件，然后检查顶层的配置项和所有配置方案
+///
+/// 缺少的可选项和无效的值使用默认值，这类问题作为警告随配置返回；只有无法得到可用配置时
+/// （如缺少必需的键、当前配置方案不存在）才返回错误，错误中一次性报告所有问题。
+/// 每个问题都带有在文件中的位置。返回的配置中，各个方案共用的配置项来自顶层，
+/// 其余配置项来自当前使用的配置方案。
+pub fn read_config(config_path: impl AsRef<Path>) -> Result<Config> {
+    fn inner(config_path: &Path) -> Result<Config> {
+        let mut text = read_text(config_path)?;
+        let doc: toml_edit::DocumentMut = parse_text(&text, config_path)?;
+        if let Some(migrated) = config_migration::migrate_config(config_path, &text, doc)? {
+            text = migrated;
+        }
+        let doc: toml_edit::ImDocument<String> = parse_text(&text, config_path)?;
+        let root = doc.as_table();
+        let span_of = |span: Option<std::ops::Range<usize>>| {
+            return span.map(|range| TextSpan::from_byte_range(&text, range));
+        };
+        let mut findings = Findings::default();
+        let values = validate_table(root, GLOBAL_SCHEMA, "", &text, &mut findings);
+        let [stardict_bundle, active_profile] =
+            <[String; 2]>::try_from(values).expect("values should match GLOBAL_SCHEMA");
+        // 写在顶层的方案配置项不会生效，很可能是忘了移到配置方案中
+        for key_schema in PROFILE_SCHEMA {
+            if let Some(key) = root.key(key_schema.key) {
+                findings.warnings.push(Issue {
+                    key: Some(key_schema.key.to_string()),
+                    message: "key outside of profiles has no effect".to_string(),
+                    span: span_of(key.span()),
+                    hint: Some(format!(
+                        r#"move it into [{PROFILES_KEY}."{active_profile}"]"#
+                    )),
+                });
+            }
+        }
+        let mut profiles = vec![];
+        let mut active_values = None;
+        for (name, table) in profile_tables(root, &text, &mut findings) {
+            let prefix = format!("{PROFILES_KEY}.{name}.");
+            let values = validate_table(table, PROFILE_SCHEMA, &prefix, &text, &mut findings);
+            if name == active_profile {
+                active_values = Some(values);
+            }
+            profiles.push(name.to_string());
+        }
+        if active_values.is_none() && !profiles.is_empty() {
+            findings.errors.push(Issue {
+                key: Some(ACTIVE_PROFILE_KEY.to_string()),
+                message: format!(r#"profile "{active_profile}" does not exist"#),
+                span: span_of(root.get(ACTIVE_PROFILE_KEY).and_then(|item| item.span())),
+                hint: Some(format!("available profiles: {}", profiles.join(", "))),
+            });
+        }
+        let (Some(values), true) = (active_values, findings.errors.is_empty()) else {
+            return Err(invalid_config(config_path, findings));
+        };
+        let [anki_connect_url, deck_name, model_name] =
+            <[String; 3]>::try_from(values).expect("values should match PROFILE_SCHEMA");
         return Ok(Config {
-            anki_connect_url: anki_connect_url.to_string(),
-            deck_name: deck_name.to_string(),
-            model_name: model_name.to_string(),
+            anki_connect_url,
+            deck_name,
+            model_name,
+            stardict_bundle,
+            active_profile,
+            profiles,
+            revision: revision_of(&text),
+            warnings: findings.warnings,
         });
     }
     return inner(config_path.as_ref());
 }
 
-pub fn commit_config(config_path: impl AsRef<Path>, modified: PartialConfig) -> Result<(), String> {
-    fn inner(config_path: &Path, modified: PartialConfig) -> Result<(), String> {
-        let toml_string = std::fs::read_to_string(config_path)
-            .map_err(|e| format!("failed to read config file {}: {e}", config_path.display()))?;
-        let mut doc = toml_string.parse::<toml_edit::DocumentMut>().map_err(|e| {
-            format!(
-                "failed to parse toml from config file {}: {e}",
-                config_path.display()
+/// 检查要写入配置文件的值，有问题时一次性报告所有问题
+fn validate_modified(modified: &PartialConfig) -> Result<()> {
+    let values = [
+        ("anki-connect-url", &modified.anki_connect_url),
+        ("deck-name", &modified.deck_name),
+        ("model-name", &modified.model_name),
+        ("stardict-bundle", &modified.stardict_bundle),
+    ];
+    let issues: Vec<Issue> = values
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let key_schema = PROFILE_SCHEMA
+                .iter()
+                .chain(GLOBAL_SCHEMA)
+                .find(|key_schema| key_schema.key == key)
+                .expect("modified key should be in schema");
+            let message = check_value(key_schema.kind, value.as_deref()?).err()?;
+            return Some(Issue {
+                key: Some(key.to_string()),
+                message,
+                span: None,
+                hint: None,
+            });
+        })
+        .collect();
+    if !issues.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidValue,
+            format!("modified config has {} problem(s)", issues.len()),
+        )
+        .with_issues(issues));
+    }
+    return Ok(());
+}
+
+/// 设置表中的字符串值，保留原值两侧的空白和行尾注释
+fn set_string(table: &mut toml_edit::Table, key: &str, value: &str) {
+    match table.get_mut(key).and_then(|item| item.as_value_mut()) {
+        Some(old) => {
+            let decor = old.decor().clone();
+            *old = toml_edit::Value::from(value);
+            *old.decor_mut() = decor;
+        }
+        None => {
+            table.insert(key, toml_edit::value(value));
+        }
+    }
+}
+
+/// 配置文件中记录的当前配置方案名
+fn active_profile_name(doc: &toml_edit::DocumentMut) -> String {
+    return doc
+        .get(ACTIVE_PROFILE_KEY)
+        .and_then(|item| item.as_str())
+        .unwrap_or(DEFAULT_PROFILE)
+        .to_string();
+}
+
+/// 配置文件中存放配置方案的表，不存在时创建
+fn profiles_mut<'a>(
+    doc: &'a mut toml_edit::DocumentMut,
+    config_path: &Path,
+) -> Result<&'a mut toml_edit::Table> {
+    return doc
+        .as_table_mut()
+        .entry(PROFILES_KEY)
+        .or_insert_with(|| {
+            let mut profiles = toml_edit::Table::new();
+            profiles.set_implicit(true);
+            return toml_edit::Item::Table(profiles);
+        })
+        .as_table_mut()
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidValue,
+                format!(r#""{PROFILES_KEY}" in config file is not a table"#),
             )
-        })?;
-        if let Some(anki_connect_url) = modified.anki_connect_url {
-            doc["anki-connect-url"] = toml_edit::value(anki_connect_url);
+            .with_path(config_path)
+        });
+}
+
+fn profile_not_found(name: &str, config_path: &Path) -> Error {
+    return Error::new(
+        ErrorKind::NotFound,
+        format!(r#"profile "{name}" does not exist"#),
+    )
+    .with_path(config_path);
+}
+
+/// 配置文件中名为 `name` 的配置方案，不存在时返回 `NotFound` 错误
+fn profile_mut<'a>(
+    doc: &'a mut toml_edit::DocumentMut,
+    name: &str,
+    config_path: &Path,
+) -> Result<&'a mut toml_edit::Table> {
+    return profiles_mut(doc, config_path)?
+        .get_mut(name)
+        .and_then(|item| item.as_table_mut())
+        .ok_or_else(|| profile_not_found(name, config_path));
+}
+
+/// 检查新的配置方案名，返回去掉首尾空白后的名称
+fn check_profile_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().any(char::is_control) {
+        return Err(Error::new(
+            ErrorKind::InvalidValue,
+            format!("{name:?} is not a valid profile name"),
+        ));
+    }
+    return Ok(name);
+}
+
+/// 原子地写回修改后的配置文件，返回写入后的文件标识
+fn write_document(config_path: &Path, doc: &toml_edit::DocumentMut) -> Result<String> {
+    let text = doc.to_string();
+    utils::write_file_atomically(config_path, &text)
+        .map_err(|e| Error::io("failed to write to config file", e).with_path(config_path))?;
+    return Ok(revision_of(&text));
+}
+
+/// 修改配置文件：方案配置项写入当前使用的配置方案，共用的配置项写入顶层，文件的其余部分保持不变
+///
+/// `revision` 为界面读取配置时的文件标识，以下修改配置文件的函数都在文件被其他程序修改过时返回
+/// `ConfigConflict` 错误，成功时返回写入后的文件标识。
+pub fn commit_config(
+    config_path: impl AsRef<Path>,
+    modified: PartialConfig,
+    revision: &str,
+) -> Result<String> {
+    fn inner(config_path: &Path, modified: PartialConfig, revision: &str) -> Result<String> {
+        validate_modified(&modified)?;
+        let mut doc = read_document(config_path, revision)?;
+        if let Some(stardict_bundle) = &modified.stardict_bundle {
+            set_string(doc.as_table_mut(), "stardict-bundle", stardict_bundle);
+        }
+        let active_profile = active_profile_name(&doc);
+        let profile = profile_mut(&mut doc, &active_profile, config_path)?;
+        if let Some(anki_connect_url) = &modified.anki_connect_url {
+            set_string(profile, "anki-connect-url", anki_connect_url);
+        }
+        if let Some(deck_name) = &modified.deck_name {
+            set_string(profile, "deck-name", deck_name);
+        }
+        if let Some(model_name) = &modified.model_name {
+            set_string(profile, "model-name", model_name);
+        }
+        return write_document(config_path, &doc);
+    }
+    return inner(config_path.as_ref(), modified, revision);
+}
+
+/// 配置文件中的所有配置方案名，按在配置文件中的顺序排列
+pub fn list_profiles(config_path: impl AsRef<Path>) -> Result<Vec<String>> {
+    fn inner(config_path: &Path) -> Result<Vec<String>> {
+        let text = read_text(config_path)?;
+        let doc: toml_edit::ImDocument<String> = parse_text(&text, config_path)?;
+        let mut findings = Findings::default();
+        let profiles = profile_tables(doc.as_table(), &text, &mut findings)
+            .into_iter()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        return Ok(profiles);
+    }
+    return inner(config_path.as_ref());
+}
+
+/// 创建配置方案，`copy_from` 为 `None` 时各配置项使用默认值，否则复制该方案的配置项
+pub fn create_profile(
+    config_path: impl AsRef<Path>,
+    name: &str,
+    copy_from: Option<&str>,
+    revision: &str,
+) -> Result<String> {
+    fn inner(
+        config_path: &Path,
+        name: &str,
+        copy_from: Option<&str>,
+        revision: &str,
+    ) -> Result<String> {
+        let name = check_profile_name(name)?;
+        let mut doc = read_document(config_path, revision)?;
+        let profiles = profiles_mut(&mut doc, config_path)?;
+        if profiles.contains_key(name) {
+            return Err(Error::new(
+                ErrorKind::InvalidValue,
+                format!(r#"profile "{name}" already exists"#),
+            )
+            .with_path(config_path));
+        }
+        let mut profile = toml_edit::Table::new();
+        match copy_from {
+            Some(source) => {
+                let source = profiles
+                    .get(source)
+                    .and_then(|item| item.as_table())
+                    .ok_or_else(|| profile_not_found(source, config_path))?;
+                for (key, item) in source.iter() {
+                    profile.insert(key, item.clone());
+                }
+            }
+            None => {
+                for key_schema in PROFILE_SCHEMA {
+                    let default = key_schema.default.unwrap_or_default();
+                    profile.insert(key_schema.key, toml_edit::value(default));
+                }
+            }
+        }
+        profiles.insert(name, toml_edit::Item::Table(profile));
+        return write_document(config_path, &doc);
+    }
+    return inner(config_path.as_ref(), name, copy_from, revision);
+}
+
+/// 重命名配置方案，被重命名的是当前使用的方案时同时修改 `active-profile`
+pub fn rename_profile(
+    config_path: impl AsRef<Path>,
+    old_name: &str,
+    new_name: &str,
+    revision: &str,
+) -> Result<String> {
+    fn inner(config_path: &Path, old_name: &str, new_name: &str, revision: &str) -> Result<String> {
+        let new_name = check_profile_name(new_name)?;
+        let mut doc = read_document(config_path, revision)?;
+        let profiles = profiles_mut(&mut doc, config_path)?;
+        if new_name != old_name && profiles.contains_key(new_name) {
+            return Err(Error::new(
+                ErrorKind::InvalidValue,
+                format!(r#"profile "{new_name}" already exists"#),
+            )
+            .with_path(config_path));
         }
-        if let Some(deck_name) = modified.deck_name {
-            doc["deck-name"] = toml_edit::value(deck_name);
+        // 移除后重新插入的表保留原来的位置和注释
+        let profile = profiles
+            .remove(old_name)
+            .ok_or_else(|| profile_not_found(old_name, config_path))?;
+        profiles.insert(new_name, profile);
+        if active_profile_name(&doc) == old_name {
+            set_string(doc.as_table_mut(), ACTIVE_PROFILE_KEY, new_name);
         }
-        if let Some(model_name) = modified.model_name {
-            doc["model-name"] = toml_edit::value(model_name);
+        return write_document(config_path, &doc);
+    }
+    return inner(config_path.as_ref(), old_name, new_name, revision);
+}
+
+/// 删除配置方案，不能删除唯一的方案；删除的是当前使用的方案时切换到剩下的第一个方案
+pub fn delete_profile(config_path: impl AsRef<Path>, name: &str, revision: &str) -> Result<String> {
+    fn inner(config_path: &Path, name: &str, revision: &str) -> Result<String> {
+        let mut doc = read_document(config_path, revision)?;
+        let profiles = profiles_mut(&mut doc, config_path)?;
+        if !profiles.contains_key(name) {
+            return Err(profile_not_found(name, config_path));
         }
-        std::fs::write(&config_path, doc.to_string()).map_err(|e| {
-            format!(
-                "failed to write to config file {}: {e}",
-                config_path.display()
+        if profiles.len() == 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidValue,
+                format!(r#"profile "{name}" is the only profile and cannot be deleted"#),
             )
-        })?;
-        return Ok(());
+            .with_path(config_path));
+        }
+        profiles.remove(name);
+        let first = profiles
+            .iter()
+            .next()
+            .map(|(first, _)| first.to_string())
+            .unwrap_or_default();
+        if active_profile_name(&doc) == name {
+            set_string(doc.as_table_mut(), ACTIVE_PROFILE_KEY, &first);
+        }
+        return write_document(config_path, &doc);
+    }
+    return inner(config_path.as_ref(), name, revision);
+}
+
+/// 切换当前使用的配置方案
+pub fn switch_profile(config_path: impl AsRef<Path>, name: &str, revision: &str) -> Result<String> {
+    fn inner(config_path: &Path, name: &str, revision: &str) -> Result<String> {
+        let mut doc = read_document(config_path, revision)?;
+        profile_mut(&mut doc, name, config_path)?;
+        set_string(doc.as_table_mut(), ACTIVE_PROFILE_KEY, name);
+        return write_document(config_path, &doc);
+    }
+    return inner(config_path.as_ref(), name, revision);
+}
+
+/// 将配置文件中的路径解析为绝对路径，相对路径相对于配置文件所在的目录
+pub fn resolve_config_relative_path(
+    config_path: impl AsRef<Path>,
+    path: impl AsRef<Path>,
+) -> PathBuf {
+    let path = path.as_ref();
+    if path.is_absolute() {
+        return path.to_path_buf();
     }
-    return inner(config_path.as_ref(), modified);
+    return match config_path.as_ref().parent() {
+        Some(config_dir) => config_dir.join(path),
+        None => path.to_path_buf(),
+    };
 }
@@ -0,0 +1,255 @@
+//! 按配置文件中的 `__minimal-version__` 升级旧版本的配置文件
+//!
+//! `__minimal-version__` 是能够读取此配置文件的最低应用版本。读取配置文件时，依次执行版本号高于
+//! 文件版本的迁移步骤，通过 toml_edit 修改以保留注释和格式，然后更新版本号。
+//! 迁移前的文件备份为 `config.toml.bak`。
+
+use std::cmp::Ordering;
+use std::path::Path;
+
+use super::error::{Error, ErrorKind, Result};
+use super::utils;
+
+/// 记录配置文件版本的键
+pub const VERSION_KEY: &str = "__minimal-version__";
+
+/// 当前的配置文件版本，即最后一个迁移步骤的版本
+pub const CONFIG_VERSION: &str = "0.1.1-0";
+
+/// 当前应用的版本
+const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// 一个迁移步骤，将配置文件升级到 `version`
+struct Migration {
+    version: &'static str,
+    apply: fn(&mut toml_edit::DocumentMut),
+}
+
+/// 按版本号从低到高排列的迁移步骤
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: "0.1.0",
+        apply: migrate_to_0_1_0,
+    },
+    Migration {
+        version: "0.1.1-0",
+        apply: migrate_to_0_1_1,
+    },
+];
+
+/// 0.1.0：加入 `stardict-bundle`；牌组名和笔记模板名写成数字等非字符串值时改为字符串
+fn migrate_to_0_1_0(doc: &mut toml_edit::DocumentMut) {
+    add_key(
+        doc,
+        "stardict-bundle",
+        "",
+        "StarDict 词典的 .ifo 文件路径（相对路径相对于此文件所在的目录），留空表示不使用",
+    );
+    retype_to_string(doc, "deck-name");
+    retype_to_string(doc, "model-name");
+}
+
+/// 0.1.1-0：AnkiConnect 服务的 URL、牌组名和笔记模板名移到配置方案 `[profiles.default]` 中，
+/// 并加入记录当前配置方案的 `active-profile`
+fn migrate_to_0_1_1(doc: &mut toml_edit::DocumentMut) {
+    if doc.contains_key("profiles") {
+        return;
+    }
+    let mut profile = toml_edit::Table::new();
+    for key in ["anki-connect-url", "deck-name", "model-name"] {
+        // 键的注释随键一起移动，但表头与第一个键之间不留空行
+        if let Some((mut key, item)) = doc.remove_entry(key) {
+            let prefix = key.leaf_decor().prefix().and_then(|prefix| prefix.as_str());
+            if let (true, Some(prefix)) = (profile.is_empty(), prefix) {
+                let prefix = prefix.trim_start_matches(['\r', '\n']).to_string();
+                key.leaf_decor_mut().set_prefix(prefix);
+            }
+            profile.insert_formatted(&key, item);
+        }
+    }
+    profile.decor_mut().set_prefix(
+        "\n# 配置方案：每个方案有各自的 AnkiConnect 服务、牌组和笔记模板，可以添加多个 [profiles.<名称>]\n",
+    );
+    let mut profiles = toml_edit::Table::new();
+    profiles.set_implicit(true);
+    profiles.insert("default", toml_edit::Item::Table(profile));
+    doc.insert("profiles", toml_edit::Item::Table(profiles));
+    add_key(
+        doc,
+        "active-profile",
+        "default",
+        "当前使用的配置方案，对应下方的一个 [profiles.<名称>]",
+    );
+}
+
+/// 键不存在时添加键，并在其上方添加注释
+fn add_key(doc: &mut toml_edit::DocumentMut, key: &str, value: &str, comment: &str) {
+    if doc.contains_key(key) {
+        return;
+    }
+    doc[key] = toml_edit::value(value);
+    if let Some(mut key) = doc.key_mut(key) {
+        key.leaf_decor_mut().set_prefix(format!("\n# {comment}\n"));
+    }
+}
+
+/// 将整数、浮点数或布尔值改为对应的字符串，保留值两侧的空白和注释
+fn retype_to_string(doc: &mut toml_edit::DocumentMut, key: &str) {
+    let Some(value) = doc.get_mut(key).and_then(|item| item.as_value_mut()) else {
+        return;
+    };
+    let text = match value {
+        toml_edit::Value::Integer(v) => v.value().to_string(),
+        toml_edit::Value::Float(v) => v.value().to_string(),
+        toml_edit::Value::Boolean(v) => v.value().to_string(),
+        _ => return,
+    };
+    let decor = value.decor().clone();
+    *value = toml_edit::Value::from(text);
+    *value.decor_mut() = decor;
+}
+
+/// 语义化版本号，只比较 `major.minor.patch` 和预发布标识（忽略构建元数据）
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Version {
+    numbers: [u64; 3],
+    /// 预发布标识，如 `0.1.1-0` 中的 `0`
+    pre: Vec<String>,
+}
+
+impl Version {
+    fn parse(version: &str) -> Option<Self> {
+        let version = version.trim();
+        let version = version.split_once('+').map_or(version, |(v, _)| v);
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, pre.split('.').map(str::to_string).collect()),
+            None => (version, vec![]),
+        };
+        let mut numbers = core.split('.').map(|n| n.parse::<u64>().ok());
+        let parsed = [numbers.next()??, numbers.next()??, numbers.next()??];
+        if numbers.next().is_some() {
+            return None;
+        }
+        return Some(Version {
+            numbers: parsed,
+            pre,
+        });
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let ordering = self.numbers.cmp(&other.numbers);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        // 有预发布标识的版本低于正式版本，预发布标识中的数字部分按数值比较
+        return match (self.pre.is_empty(), other.pre.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => {
+                for (a, b) in self.pre.iter().zip(&other.pre) {
+                    let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+                        (Ok(a), Ok(b)) => a.cmp(&b),
+                        (Ok(_), Err(_)) => Ordering::Less,
+                        (Err(_), Ok(_)) => Ordering::Greater,
+                        (Err(_), Err(_)) => a.cmp(b),
+                    };
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                }
+                self.pre.len().cmp(&other.pre.len())
+            }
+        };
+    }
+}
+
+fn parse_known_version(version: &str) -> Version {
+    return Version::parse(version).expect("built-in version should be valid");
+}
+
+/// 检查配置文件的版本，需要时执行迁移并写回文件，执行了迁移时返回迁移后的文件内容
+///
+/// `text` 为配置文件的原始内容（用于备份），`doc` 为其解析结果。
+/// 配置文件没有版本号时视为最旧的版本；版本号高于当前应用版本时返回错误，不修改文件。
+pub fn migrate_config(
+    config_path: impl AsRef<Path>,
+    text: &str,
+    doc: toml_edit::DocumentMut,
+) -> Result<Option<String>> {
+    fn inner(
+        config_path: &Path,
+        text: &str,
+        mut doc: toml_edit::DocumentMut,
+    ) -> Result<Option<String>> {
+        let file_version = match doc.get(VERSION_KEY) {
+            None => None,
+            Some(item) => {
+                let version = item.as_str().and_then(Version::parse).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidValue,
+                        format!(r#""{VERSION_KEY}" is not a valid version like "0.1.0""#),
+                    )
+                    .with_path(config_path)
+                })?;
+                Some(version)
+            }
+        };
+        let app_version = parse_known_version(APP_VERSION);
+        if let Some(file_version) = &file_version {
+            if *file_version > app_version {
+                return Err(Error::new(
+                    ErrorKind::ConfigVersion,
+                    format!(
+                        "config file requires app version {} or later, but this is version {APP_VERSION}; please update the app",
+                        doc[VERSION_KEY].as_str().unwrap_or_default().trim(),
+                    ),
+                )
+                .with_path(config_path));
+            }
+        }
+        let pending: Vec<&Migration> = MIGRATIONS
+            .iter()
+            .filter(|migration| {
+                file_version
+                    .as_ref()
+                    .is_none_or(|version| parse_known_version(migration.version) > *version)
+            })
+            .collect();
+        if pending.is_empty() {
+            return Ok(None);
+        }
+        let backup_path = config_path.with_extension("toml.bak");
+        utils::write_file_atomically(&backup_path, text).map_err(|e| {
+            Error::io("failed to back up config file before migration", e).with_path(&backup_path)
+        })?;
+        for migration in pending {
+            (migration.apply)(&mut doc);
+        }
+        match doc
+            .get_mut(VERSION_KEY)
+            .and_then(|item| item.as_value_mut())
+        {
+            Some(value) => {
+                let decor = value.decor().clone();
+                *value = toml_edit::Value::from(CONFIG_VERSION);
+                *value.decor_mut() = decor;
+            }
+            None => add_key(&mut doc, VERSION_KEY, CONFIG_VERSION, "请勿手动修改此字段"),
+        }
+        let migrated = doc.to_string();
+        utils::write_file_atomically(config_path, &migrated).map_err(|e| {
+            Error::io("failed to write migrated config file", e).with_path(config_path)
+        })?;
+        return Ok(Some(migrated));
+    }
+    return inner(config_path.as_ref(), text, doc);
+}
@@ -1,15 +1,32 @@
+use std::collections::HashMap;
 use std::path::Path;
 
 use rusqlite::{Connection, OptionalExtension};
 
+use super::cache::DictCache;
+use super::error::{Error, ErrorKind, Result};
+use super::frequency::{self, FrequencySource, WordLevel};
+use super::grammar::{self, Grammar};
+use super::morphology::{self, BaseCandidate, FormKind};
+use super::overlay::UserEntry;
+use super::phonetics::{self, Phonetics};
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CollinsItem {
     word: String,
     phonetic: Option<String>,
+    /// 由 phonetic 拆分出的英式、美式音标
+    phonetics: Option<Phonetics>,
     sense: Option<String>,
     en_def: Option<String>,
     cn_def: Option<String>,
+    /// 由 sense 解析出的词性和语法标注
+    grammar: Option<Grammar>,
+    /// 是否来自用户词典
+    user_provided: bool,
+    /// 用户词典中的备注
+    notes: Option<String>,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -18,116 +35,731 @@ pub struct OxfordItem {
     word: String,
     phrase: Option<String>,
     phonetic: Option<String>,
+    /// 由 phonetic 拆分出的英式、美式音标
+    phonetics: Option<Phonetics>,
     sense: Option<String>,
     ext: Option<String>,
     en_def: Option<String>,
     cn_def: Option<String>,
+    /// 由 sense 和 ext 解析出的词性和语法标注
+    grammar: Option<Grammar>,
+    /// 是否来自用户词典
+    user_provided: bool,
+    /// 用户词典中的备注
+    notes: Option<String>,
+}
+
+impl CollinsItem {
+    fn from_row(row: &rusqlite::Row) -> Result<Self> {
+        let phonetic: Option<String> = row
+            .get("phonetic")
+            .map_err(|e| Error::sqlite("failed to get phonetic", e))?;
+        let sense: Option<String> = row
+            .get("sense")
+            .map_err(|e| Error::sqlite("failed to get sense", e))?;
+        let grammar = grammar::parse_grammar(sense.as_deref(), None);
+        return Ok(CollinsItem {
+            word: row
+                .get("word")
+                .map_err(|e| Error::sqlite("failed to get word", e))?,
+            phonetics: phonetics::parse_phonetics(phonetic.as_deref()),
+            phonetic,
+            sense,
+            en_def: row
+                .get("enDef")
+                .map_err(|e| Error::sqlite("failed to get enDef", e))?,
+            cn_def: row
+                .get("cnDef")
+                .map_err(|e| Error::sqlite("failed to get cnDef", e))?,
+            grammar,
+            user_provided: false,
+            notes: None,
+        });
+    }
+}
+
+impl From<UserEntry> for CollinsItem {
+    fn from(entry: UserEntry) -> Self {
+        return CollinsItem {
+            word: entry.word,
+            phonetic: entry.phonetic,
+            phonetics: entry.phonetics,
+            sense: entry.sense,
+            en_def: entry.en_def,
+            cn_def: entry.cn_def,
+            grammar: entry.grammar,
+            user_provided: true,
+            notes: entry.notes,
+        };
+    }
+}
+
+impl OxfordItem {
+    fn from_row(row: &rusqlite::Row) -> Result<Self> {
+        let phonetic: Option<String> = row
+            .get("phonetic")
+            .map_err(|e| Error::sqlite("failed to get phonetic", e))?;
+        let sense: Option<String> = row
+            .get("sense")
+            .map_err(|e| Error::sqlite("failed to get sense", e))?;
+        let ext: Option<String> = row
+            .get("ext")
+            .map_err(|e| Error::sqlite("failed to get ext", e))?;
+        let grammar = grammar::parse_grammar(sense.as_deref(), ext.as_deref());
+        return Ok(OxfordItem {
+            word: row
+                .get("word")
+                .map_err(|e| Error::sqlite("failed to get word", e))?,
+            phrase: row
+                .get("phrase")
+                .map_err(|e| Error::sqlite("failed to get phrase", e))?,
+            phonetics: phonetics::parse_phonetics(phonetic.as_deref()),
+            phonetic,
+            sense,
+            ext,
+            en_def: row
+                .get("enDef")
+                .map_err(|e| Error::sqlite("failed to get enDef", e))?,
+            cn_def: row
+                .get("cnDef")
+                .map_err(|e| Error::sqlite("failed to get cnDef", e))?,
+            grammar,
+            user_provided: false,
+            notes: None,
+        });
+    }
+}
+
+impl From<&UserEntry> for OxfordItem {
+    fn from(entry: &UserEntry) -> Self {
+        return OxfordItem {
+            word: entry.word.clone(),
+            phrase: None,
+            phonetic: entry.phonetic.clone(),
+            phonetics: entry.phonetics.clone(),
+            sense: entry.sense.clone(),
+            ext: None,
+            en_def: entry.en_def.clone(),
+            cn_def: entry.cn_def.clone(),
+            grammar: entry.grammar.clone(),
+            user_provided: true,
+            notes: entry.notes.clone(),
+        };
+    }
+}
+
+/// 句子中一个词的查询结果
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenLookup {
+    token: String,
+    /// 查询时使用的原型，若为 `None` 则只查询了 `token` 本身
+    base: Option<String>,
+    /// `token` 及其原型在 collins 中的词条
+    collins: Vec<CollinsItem>,
+    /// `token` 及其原型在 oxford 中的词条
+    oxford: Vec<OxfordItem>,
+    /// 词频与难度，没有词频数据时为 `None`
+    level: Option<WordLevel>,
+}
+
+impl TokenLookup {
+    /// 查询时使用的单词，即 `token` 及其原型（若有）
+    pub fn words(&self) -> Vec<&str> {
+        return [Some(self.token.as_str()), self.base.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+    }
+
+    /// 合并 `token` 及其原型在用户词典中的词条
+    pub fn merge_user_entries(&mut self, entries: &[UserEntry]) {
+        self.collins = merge_user_collins(std::mem::take(&mut self.collins), entries);
+        self.oxford = merge_user_oxford(std::mem::take(&mut self.oxford), entries);
+    }
+}
+
+/// 原型的一种词形
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordForm {
+    form: String,
+    /// 词形的类型，无法推断时为空
+    kinds: Vec<FormKind>,
+    /// 是否由规则生成（而非 forms 表中收录的）
+    generated: bool,
+}
+
+/// 自动补全的候选词
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutocompleteItem {
+    word: String,
+    in_collins: bool,
+    in_oxford: bool,
+}
+
+/// 词典的基本信息
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DictionaryInfo {
+    pub id: String,
+    pub name: String,
+    /// 词头的语言，如 "en"
+    pub source_language: Option<String>,
+    /// 释义的语言，如 "zh"
+    pub target_language: Option<String>,
+    /// 是否为 dict.db 中自带的词典
+    pub builtin: bool,
 }
 
-pub fn open_connection(dict_path: impl AsRef<Path>) -> Result<Connection, String> {
-    fn inner(dict_path: &Path) -> Result<Connection, String> {
+/// 通用的词条格式，各个词典的查询结果都会转换为此格式
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DictionaryEntry {
+    pub word: String,
+    pub phonetic: Option<String>,
+    /// 由 phonetic 拆分出的英式、美式音标
+    pub phonetics: Option<Phonetics>,
+    pub sense: Option<String>,
+    /// 源语言的释义
+    pub definition: Option<String>,
+    /// 目标语言的释义
+    pub translation: Option<String>,
+    /// HTML 格式的完整词条（如 MDict 词典），需由前端渲染
+    pub html: Option<String>,
+    /// 由 sense 解析出的词性和语法标注
+    pub grammar: Option<Grammar>,
+    /// 是否来自用户词典
+    pub user_provided: bool,
+}
+
+impl From<CollinsItem> for DictionaryEntry {
+    fn from(item: CollinsItem) -> Self {
+        return DictionaryEntry {
+            word: item.word,
+            phonetic: item.phonetic,
+            phonetics: item.phonetics,
+            sense: item.sense,
+            definition: item.en_def,
+            translation: item.cn_def,
+            html: None,
+            grammar: item.grammar,
+            user_provided: item.user_provided,
+        };
+    }
+}
+
+impl From<OxfordItem> for DictionaryEntry {
+    fn from(item: OxfordItem) -> Self {
+        return DictionaryEntry {
+            word: item.word,
+            phonetic: item.phonetic,
+            phonetics: item.phonetics,
+            sense: [item.sense, item.ext]
+                .into_iter()
+                .flatten()
+                .reduce(|sense, ext| format!("{sense} {ext}")),
+            definition: item.en_def,
+            translation: item.cn_def,
+            html: None,
+            grammar: item.grammar,
+            user_provided: item.user_provided,
+        };
+    }
+}
+
+/// 可查询的词典，除自带的 collins 和 oxford 外，其余词典都通过此 trait 接入
+pub trait Dictionary: Send + Sync {
+    fn info(&self) -> &DictionaryInfo;
+
+    /// 查询词头（不区分大小写）
+    fn search(&self, word: &str) -> Result<Vec<DictionaryEntry>>;
+
+    /// 获取词条中引用的资源（如图片、音频），不存在时返回 `None`
+    fn resource(&self, _path: &str) -> Result<Option<Vec<u8>>> {
+        return Ok(None);
+    }
+}
+
+pub fn open_connection(dict_path: impl AsRef<Path>) -> Result<Connection> {
+    fn inner(dict_path: &Path) -> Result<Connection> {
         use rusqlite::OpenFlags;
         let flags = OpenFlags::SQLITE_OPEN_READ_ONLY
             | OpenFlags::SQLITE_OPEN_URI
             | OpenFlags::SQLITE_OPEN_NO_MUTEX;
-        let conn = Connection::open_with_flags(dict_path, flags)
-            .map_err(|e| format!("failed to open resources/dict.db, error: {e}"))?;
+        let conn = Connection::open_with_flags(dict_path, flags).map_err(|e| {
+            Error::sqlite("failed to open dictionary database", e).with_path(dict_path)
+        })?;
         return Ok(conn);
     }
     return inner(dict_path.as_ref());
 }
 
-pub fn search_collins(
-    conn: &Connection,
-    word: impl AsRef<str>,
-) -> Result<Vec<CollinsItem>, String> {
-    fn inner(conn: &Connection, word: &str) -> Result<Vec<CollinsItem>, String> {
+/// 用文件大小和修改时间标识 dict.db，dict.db 被替换后，依赖其内容的索引和缓存都需要重建
+pub fn dict_fingerprint(dict_path: impl AsRef<Path>) -> Result<String> {
+    let dict_path = dict_path.as_ref();
+    let metadata = std::fs::metadata(dict_path)
+        .map_err(|e| Error::io("failed to read metadata", e).with_path(dict_path))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_nanos());
+    return Ok(format!("{}-{modified}", metadata.len()));
+}
+
+/// dict.db 中必须存在的表及其列
+const DICT_SCHEMA: [(&str, &[&str]); 3] = [
+    ("collins", &["word", "phonetic", "sense", "enDef", "cnDef"]),
+    (
+        "oxford",
+        &[
+            "word", "phrase", "phonetic", "sense", "ext", "enDef", "cnDef",
+        ],
+    ),
+    ("forms", &["word", "base"]),
+];
+
+/// 支持的 dict.db 结构版本（`PRAGMA user_version`），为 0 表示数据库未标注版本
+pub const DICT_SCHEMA_VERSION: i64 = 1;
+
+/// 检查 dict.db 是否为可用的词典数据库，并给出具体的问题
+///
+/// 依次检查：文件是否存在、是否为未下载的 Git LFS 指针文件、SQLite 文件头、文件是否被截断、
+/// 表和列是否齐全、结构版本是否受支持。
+pub fn validate_dict_db(dict_path: impl AsRef<Path>) -> Result<()> {
+    fn inner(dict_path: &Path) -> Result<()> {
+        use std::io::Read;
+        let invalid = |message: &str| -> Error {
+            return Error::invalid_dictionary(message).with_path(dict_path);
+        };
+        let mut file = std::fs::File::open(dict_path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return Error::new(ErrorKind::NotFound, "dict.db does not exist")
+                    .with_path(dict_path);
+            }
+            return Error::io("failed to open dict.db", e).with_path(dict_path);
+        })?;
+        let file_size = file
+            .metadata()
+            .map_err(|e| Error::io("failed to read metadata of dict.db", e).with_path(dict_path))?
+            .len();
+        let mut header = Vec::with_capacity(100);
+        (&mut file)
+            .take(100)
+            .read_to_end(&mut header)
+            .map_err(|e| Error::io("failed to read dict.db", e).with_path(dict_path))?;
+        if header.is_empty() {
+            return Err(invalid("dict.db is empty"));
+        }
+        if header.starts_with(b"version https://git-lfs.github.com/spec/") {
+            return Err(invalid(
+                "dict.db is a Git LFS pointer rather than the database itself, \
+                run `git lfs pull` or download a release build",
+            ));
+        }
+        if !header.starts_with(b"SQLite format 3\0") {
+            return Err(invalid("dict.db is not a SQLite database"));
+        }
+        if header.len() < 100 {
+            return Err(invalid("dict.db is truncated (incomplete SQLite header)"));
+        }
+        // 页大小为 1 表示 65536
+        let page_size = match u16::from_be_bytes([header[16], header[17]]) {
+            1 => 65536,
+            size => size as u64,
+        };
+        let change_counter = &header[24..28];
+        let version_valid_for = &header[92..96];
+        // 仅当 version-valid-for 与修改计数相同时，文件头中的页数才是可信的
+        if change_counter == version_valid_for {
+            let page_count = u32::from_be_bytes([header[28], header[29], header[30], header[31]]);
+            let expected_size = page_size * page_count as u64;
+            if file_size < expected_size {
+                return Err(invalid(&format!(
+                    "dict.db is truncated ({file_size} bytes, expected {expected_size} bytes)"
+                )));
+            }
+        }
+        drop(file);
+
+        let conn = open_connection(dict_path)?;
+        for (table, columns) in DICT_SCHEMA {
+            let mut stmt = conn
+                .prepare("select name from pragma_table_info(?1)")
+                .map_err(|e| {
+                    Error::sqlite("failed to read schema of dict.db", e).with_path(dict_path)
+                })?;
+            let existing = stmt
+                .query_map([table], |row| row.get::<_, String>(0))
+                .and_then(|rows| rows.collect::<rusqlite::Result<Vec<String>>>())
+                .map_err(|e| {
+                    Error::sqlite("failed to read schema of dict.db", e).with_path(dict_path)
+                })?;
+            if existing.is_empty() {
+                return Err(invalid(&format!("dict.db is missing table {table}")));
+            }
+            if let Some(column) = columns
+                .iter()
+                .find(|column| !existing.iter().any(|c| c.eq_ignore_ascii_case(column)))
+            {
+                return Err(invalid(&format!("table {table} missing column {column}")));
+            }
+        }
+        let version: i64 = conn
+            .query_row("pragma user_version", [], |row| row.get(0))
+            .map_err(|e| {
+                Error::sqlite("failed to read schema version of dict.db", e).with_path(dict_path)
+            })?;
+        if version > DICT_SCHEMA_VERSION {
+            return Err(invalid(&format!(
+                "dict.db uses schema version {version}, \
+                but this version of the app only supports up to {DICT_SCHEMA_VERSION}"
+            )));
+        }
+        return Ok(());
+    }
+    return inner(dict_path.as_ref());
+}
+
+pub fn search_collins(conn: &Connection, word: impl AsRef<str>) -> Result<Vec<CollinsItem>> {
+    fn inner(conn: &Connection, word: &str) -> Result<Vec<CollinsItem>> {
         let mut stmt = conn
             .prepare_cached("select * from collins where word = ?1 collate nocase order by rowid")
-            .map_err(|e| format!("failed to prepare SQL statement for collins search: {e}"))?;
+            .map_err(|e| Error::sqlite("failed to prepare SQL statement for collins search", e))?;
         let mut rows = stmt
             .query([word])
-            .map_err(|e| format!("failed to query collins: {e}"))?;
+            .map_err(|e| Error::sqlite("failed to query collins", e))?;
         let mut items = vec![];
         while let Some(row) = rows
             .next()
-            .map_err(|e| format!("failed to get next row from collins search result: {e}"))?
+            .map_err(|e| Error::sqlite("failed to get next row from collins search result", e))?
         {
-            items.push(CollinsItem {
-                word: row
-                    .get("word")
-                    .map_err(|e| format!("failed to get word: {e}"))?,
-                phonetic: row
-                    .get("phonetic")
-                    .map_err(|e| format!("failed to get phonetic: {e}"))?,
-                sense: row
-                    .get("sense")
-                    .map_err(|e| format!("failed to get sense: {e}"))?,
-                en_def: row
-                    .get("enDef")
-                    .map_err(|e| format!("failed to get enDef: {e}"))?,
-                cn_def: row
-                    .get("cnDef")
-                    .map_err(|e| format!("failed to get cnDef: {e}"))?,
-            });
+            items.push(CollinsItem::from_row(row)?);
         }
         return Ok(items);
     }
     return inner(conn, word.as_ref());
 }
 
-pub fn search_oxford(conn: &Connection, word: impl AsRef<str>) -> Result<Vec<OxfordItem>, String> {
-    fn inner(conn: &Connection, word: &str) -> Result<Vec<OxfordItem>, String> {
+pub fn search_oxford(conn: &Connection, word: impl AsRef<str>) -> Result<Vec<OxfordItem>> {
+    fn inner(conn: &Connection, word: &str) -> Result<Vec<OxfordItem>> {
         let mut stmt = conn
             .prepare_cached("select * from oxford where word = ?1 collate nocase order by rowid")
-            .map_err(|e| format!("failed to prepare SQL statement for oxford search: {e}"))?;
+            .map_err(|e| Error::sqlite("failed to prepare SQL statement for oxford search", e))?;
         let mut rows = stmt
             .query([word])
-            .map_err(|e| format!("failed to query oxford: {e}"))?;
+            .map_err(|e| Error::sqlite("failed to query oxford", e))?;
         let mut items = vec![];
         while let Some(row) = rows
             .next()
-            .map_err(|e| format!("failed to get next row from oxford search result: {e}"))?
+            .map_err(|e| Error::sqlite("failed to get next row from oxford search result", e))?
         {
-            items.push(OxfordItem {
-                word: row
-                    .get("word")
-                    .map_err(|e| format!("failed to get word: {e}"))?,
-                phrase: row
-                    .get("phrase")
-                    .map_err(|e| format!("failed to get phrase: {e}"))?,
-                phonetic: row
-                    .get("phonetic")
-                    .map_err(|e| format!("failed to get phonetic: {e}"))?,
-                sense: row
-                    .get("sense")
-                    .map_err(|e| format!("failed to get sense: {e}"))?,
-                ext: row
-                    .get("ext")
-                    .map_err(|e| format!("failed to get ext: {e}"))?,
-                en_def: row
-                    .get("enDef")
-                    .map_err(|e| format!("failed to get enDef: {e}"))?,
-                cn_def: row
-                    .get("cnDef")
-                    .map_err(|e| format!("failed to get cnDef: {e}"))?,
-            });
+            items.push(OxfordItem::from_row(row)?);
         }
         return Ok(items);
     }
     return inner(conn, word.as_ref());
 }
 
+/// 按 rowid 获取 collins 表中的词条
+pub fn get_collins_by_rowid(conn: &Connection, rowid: i64) -> Result<Option<CollinsItem>> {
+    let mut stmt = conn
+        .prepare_cached("select * from collins where rowid = ?1")
+        .map_err(|e| {
+            Error::sqlite(
+                "failed to prepare SQL statement for collins rowid search",
+                e,
+            )
+        })?;
+    let item = stmt
+        .query_row([rowid], |row| Ok(CollinsItem::from_row(row)))
+        .optional()
+        .map_err(|e| Error::sqlite("failed to query collins by rowid", e))?
+        .transpose()?;
+    return Ok(item);
+}
+
+/// 按 rowid 获取 oxford 表中的词条
+pub fn get_oxford_by_rowid(conn: &Connection, rowid: i64) -> Result<Option<OxfordItem>> {
+    let mut stmt = conn
+        .prepare_cached("select * from oxford where rowid = ?1")
+        .map_err(|e| Error::sqlite("failed to prepare SQL statement for oxford rowid search", e))?;
+    let item = stmt
+        .query_row([rowid], |row| Ok(OxfordItem::from_row(row)))
+        .optional()
+        .map_err(|e| Error::sqlite("failed to query oxford by rowid", e))?
+        .transpose()?;
+    return Ok(item);
+}
+
 /// 获取单词的原型
-pub fn get_word_base(conn: &Connection, word: impl AsRef<str>) -> Result<Option<String>, String> {
-    fn inner(conn: &Connection, word: &str) -> Result<Option<String>, String> {
+pub fn get_word_base(conn: &Connection, word: impl AsRef<str>) -> Result<Option<String>> {
+    fn inner(conn: &Connection, word: &str) -> Result<Option<String>> {
         let mut stmt = conn
             .prepare_cached("select * from forms where word = ?1 collate nocase")
-            .map_err(|e| format!("failed to prepare SQL statement for word base search: {e}"))?;
+            .map_err(|e| {
+                Error::sqlite("failed to prepare SQL statement for word base search", e)
+            })?;
         let base: Option<String> = stmt
             .query_row([word], |row| row.get("base"))
             .optional()
-            .map_err(|e| format!("failed to query word base: {e}"))?;
+            .map_err(|e| Error::sqlite("failed to query word base", e))?;
+        return Ok(base);
+    }
+    return inner(conn, word.as_ref());
+}
+
+/// 查找以 `prefix` 开头的词头（不区分大小写），合并 collins 和 oxford 两张表的结果
+///
+/// 只有大小写不同的词头只返回一个：优先使用大小写与 `prefix` 一致的写法，否则优先使用小写的写法。
+///
+/// 排序规则：大小写与 `prefix` 完全一致的排在前面，其次是较短的单词，最后按字母顺序。
+pub fn autocomplete(
+    conn: &Connection,
+    prefix: impl AsRef<str>,
+    limit: usize,
+) -> Result<Vec<AutocompleteItem>> {
+    fn inner(conn: &Connection, prefix: &str, limit: usize) -> Result<Vec<AutocompleteItem>> {
+        if prefix.is_empty() || limit == 0 {
+            return Ok(vec![]);
+        }
+        // 转义 LIKE 中的通配符
+        let pattern = format!(
+            "{}%",
+            prefix
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        let mut stmt = conn
+            .prepare_cached(
+                "select word, in_collins, in_oxford from (
+                    select coalesce(max(case when substr(word, 1, length(?2)) = ?2 then word end), max(word)) as word,
+                        max(in_collins) as in_collins, max(in_oxford) as in_oxford
+                    from (
+                        select word, 1 as in_collins, 0 as in_oxford from collins where word like ?1 escape '\\'
+                        union all
+                        select word, 0 as in_collins, 1 as in_oxford from oxford where word like ?1 escape '\\'
+                    )
+                    group by word collate nocase
+                )
+                order by substr(word, 1, length(?2)) = ?2 desc, length(word), word
+                limit ?3",
+            )
+            .map_err(|e| Error::sqlite("failed to prepare SQL statement for autocomplete", e))?;
+        let mut rows = stmt
+            .query(rusqlite::params![pattern, prefix, limit as i64])
+            .map_err(|e| Error::sqlite("failed to query autocomplete candidates", e))?;
+        let mut items = vec![];
+        while let Some(row) = rows
+            .next()
+            .map_err(|e| Error::sqlite("failed to get next row from autocomplete result", e))?
+        {
+            items.push(AutocompleteItem {
+                word: row
+                    .get("word")
+                    .map_err(|e| Error::sqlite("failed to get word", e))?,
+                in_collins: row
+                    .get("in_collins")
+                    .map_err(|e| Error::sqlite("failed to get in_collins", e))?,
+                in_oxford: row
+                    .get("in_oxford")
+                    .map_err(|e| Error::sqlite("failed to get in_oxford", e))?,
+            });
+        }
+        return Ok(items);
+    }
+    return inner(conn, prefix.as_ref(), limit);
+}
+
+/// 将用户词典中的词条合并到 collins 的查询结果中
+///
+/// 用户词条排在自带的词条之前；有词条代替自带的词条时，不再返回 collins 中的词条。
+pub fn merge_user_collins(items: Vec<CollinsItem>, entries: &[UserEntry]) -> Vec<CollinsItem> {
+    let replaced = entries.iter().any(|entry| entry.replace_builtin);
+    let mut merged: Vec<CollinsItem> = entries.iter().cloned().map(CollinsItem::from).collect();
+    if !replaced {
+        merged.extend(items);
+    }
+    return merged;
+}
+
+/// 将用户词典中的词条合并到 oxford 的查询结果中
+///
+/// 用户词条排在自带的词条之前；有词条代替自带的词条时，不再返回 oxford 中的词条。
+pub fn merge_user_oxford(items: Vec<OxfordItem>, entries: &[UserEntry]) -> Vec<OxfordItem> {
+    let replaced = entries.iter().any(|entry| entry.replace_builtin);
+    let mut merged: Vec<OxfordItem> = entries.iter().map(OxfordItem::from).collect();
+    if !replaced {
+        merged.extend(items);
+    }
+    return merged;
+}
+
+/// 在同一个事务中查询句子中所有词的原型以及 collins、oxford 词条
+///
+/// 每个词会同时查询其本身和原型（若有），与前端逐个调用 `get_word_base`、`search_collins`、
+/// `search_oxford` 的结果一致。句子中重复出现的词只查询一次。`generation` 为取出 `conn` 时连接池的 generation。
+pub fn lookup_tokens(
+    conn: &Connection,
+    generation: u64,
+    cache: &DictCache,
+    frequency: Option<&FrequencySource>,
+    tokens: &[String],
+) -> Result<Vec<TokenLookup>> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| Error::sqlite("failed to begin transaction for sentence lookup", e))?;
+    let mut looked_up: HashMap<String, TokenLookup> = HashMap::new();
+    let mut results = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let key = token.to_lowercase();
+        if let Some(lookup) = looked_up.get(&key) {
+            results.push(TokenLookup {
+                token: token.clone(),
+                ..lookup.clone()
+            });
+            continue;
+        }
+        let base = resolve_word_base(&tx, token)?;
+        let mut collins = cache.search_collins(&tx, generation, token)?;
+        let mut oxford = cache.search_oxford(&tx, generation, token)?;
+        if let Some(base) = &base {
+            collins.extend(cache.search_collins(&tx, generation, base)?);
+            oxford.extend(cache.search_oxford(&tx, generation, base)?);
+        }
+        let level = match frequency {
+            Some(frequency) => frequency::word_level(frequency, token, base.as_deref())?,
+            None => None,
+        };
+        let lookup = TokenLookup {
+            token: token.clone(),
+            base,
+            collins,
+            oxford,
+            level,
+        };
+        looked_up.insert(key, lookup.clone());
+        results.push(lookup);
+    }
+    tx.commit()
+        .map_err(|e| Error::sqlite("failed to commit sentence lookup transaction", e))?;
+    return Ok(results);
+}
+
+/// 获取原型的所有词形，包括 forms 表中收录的词形，以及按规则生成的、forms 表中没有同类词形的词形
+pub fn get_word_forms(conn: &Connection, base: impl AsRef<str>) -> Result<Vec<WordForm>> {
+    fn inner(conn: &Connection, base: &str) -> Result<Vec<WordForm>> {
+        let mut stmt = conn
+            .prepare_cached("select word from forms where base = ?1 collate nocase order by rowid")
+            .map_err(|e| {
+                Error::sqlite("failed to prepare SQL statement for word forms search", e)
+            })?;
+        let mut rows = stmt
+            .query([base])
+            .map_err(|e| Error::sqlite("failed to query word forms", e))?;
+        let mut forms: Vec<WordForm> = vec![];
+        let contains = |forms: &[WordForm], form: &str| {
+            form.eq_ignore_ascii_case(base)
+                || forms.iter().any(|f| f.form.eq_ignore_ascii_case(form))
+        };
+        while let Some(row) = rows
+            .next()
+            .map_err(|e| Error::sqlite("failed to get next row from word forms search result", e))?
+        {
+            let form: String = row
+                .get("word")
+                .map_err(|e| Error::sqlite("failed to get word", e))?;
+            if contains(&forms, &form) {
+                continue;
+            }
+            forms.push(WordForm {
+                kinds: morphology::infer_form_kinds(base, &form),
+                form,
+                generated: false,
+            });
+        }
+        // forms 表可能只收录了部分词形，其余词形按规则补全；但 forms 表中已有同类词形时不再生成，
+        // 以免在 "went" 旁边生成 "goed" 之类的错误词形
+        let mut covered: Vec<FormKind> = forms
+            .iter()
+            .flat_map(|form| form.kinds.iter().copied())
+            .collect();
+        // 无法推断类型的词形是不规则变化，不知道它代替了哪种规则词形，此时只补全总是规则变化的 -ing 形式
+        if forms.iter().any(|form| form.kinds.is_empty()) {
+            covered.extend([
+                FormKind::Plural,
+                FormKind::Past,
+                FormKind::PastParticiple,
+                FormKind::Comparative,
+                FormKind::Superlative,
+            ]);
+        }
+        for (form, kinds) in morphology::regular_forms(base) {
+            if contains(&forms, &form) || kinds.iter().any(|kind| covered.contains(kind)) {
+                continue;
+            }
+            forms.push(WordForm {
+                form,
+                kinds,
+                generated: true,
+            });
+        }
+        return Ok(forms);
+    }
+    return inner(conn, base.as_ref());
+}
+
+/// 单词是否为 collins 或 oxford 中的词头
+pub fn is_headword(conn: &Connection, word: impl AsRef<str>) -> Result<bool> {
+    fn inner(conn: &Connection, word: &str) -> Result<bool> {
+        let mut stmt = conn
+            .prepare_cached(
+                "select exists(select 1 from collins where word = ?1 collate nocase)
+                or exists(select 1 from oxford where word = ?1 collate nocase)",
+            )
+            .map_err(|e| Error::sqlite("failed to prepare SQL statement for headword check", e))?;
+        let exists: bool = stmt
+            .query_row([word], |row| row.get(0))
+            .map_err(|e| Error::sqlite("failed to check headword", e))?;
+        return Ok(exists);
+    }
+    return inner(conn, word.as_ref());
+}
+
+/// 按构词规则推测单词的原型，只返回词典中存在的候选原型
+///
+/// 用于 forms 表中没有收录的词形，如规则变化、连字符复合词、英式/美式拼写差异。
+pub fn guess_word_bases(conn: &Connection, word: impl AsRef<str>) -> Result<Vec<BaseCandidate>> {
+    fn inner(conn: &Connection, word: &str) -> Result<Vec<BaseCandidate>> {
+        let mut bases = vec![];
+        for candidate in morphology::candidate_bases(word) {
+            if is_headword(conn, &candidate.base)? {
+                bases.push(candidate);
+            }
+        }
+        return Ok(bases);
+    }
+    return inner(conn, word.as_ref());
+}
+
+/// 获取单词的原型，forms 表中没有收录时按构词规则推测
+///
+/// 若单词本身就是词头，则不再推测，以免把 "news" 之类的词还原为 "new"。
+pub fn resolve_word_base(conn: &Connection, word: impl AsRef<str>) -> Result<Option<String>> {
+    fn inner(conn: &Connection, word: &str) -> Result<Option<String>> {
+        if let Some(base) = get_word_base(conn, word)? {
+            return Ok(Some(base));
+        }
+        if is_headword(conn, word)? {
+            return Ok(None);
+        }
+        let base = guess_word_bases(conn, word)?
+            .into_iter()
+            .next()
+            .map(|candidate| candidate.base);
         return Ok(base);
     }
     return inner(conn, word.as_ref());
@@ -0,0 +1,188 @@
+use std::path::Path;
+
+/// 错误的类别，前端可以据此给出针对性的处理，例如配置文件不存在时提供“重新创建配置”的按钮
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ErrorKind {
+    /// 文件读写等 I/O 错误
+    Io,
+    /// 配置文件不存在
+    ConfigMissing,
+    /// TOML 语法错误
+    TomlParse,
+    /// 配置文件由更新版本的应用写入，当前版本无法读取
+    ConfigVersion,
+    /// 配置文件在界面读取之后被其他程序修改，拒绝覆盖
+    ConfigConflict,
+    /// 缺少必需的键
+    MissingKey,
+    /// 键的值类型或取值不正确
+    InvalidValue,
+    /// SQLite 数据库错误
+    Sqlite,
+    /// 词典文件的格式不正确或不受支持
+    InvalidDictionary,
+    /// 要查询的词典或资源不存在
+    NotFound,
+    /// 当前平台不支持此操作
+    PlatformUnsupported,
+    /// 文件监视器错误
+    Watcher,
+    /// 所需的索引正在后台建立，稍后重试即可
+    NotReady,
+    /// 其他内部错误
+    Internal,
+}
+
+/// 后端的错误，序列化后传给前端的格式为 `{kind, message, path?, detail?}`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// 对错误的简短描述
+    pub message: String,
+    /// 与错误相关的文件路径
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// 底层错误的信息，如操作系统或 SQLite 给出的错误
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// 错误涉及的各处具体问题，如配置文件中所有不正确的键
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub issues: Vec<Issue>,
+}
+
+/// 错误涉及的一处具体问题
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Issue {
+    /// 问题所在的键，如 `deck-name`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub message: String,
+    /// 问题在文件中的位置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<TextSpan>,
+    /// 修正建议
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+/// 文件中的一段文本，`end` 不包含在内
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextSpan {
+    pub start: TextPosition,
+    pub end: TextPosition,
+}
+
+/// 文件中的位置，行号和列号都从 1 开始，列号按字符计算
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl TextSpan {
+    /// 由字节偏移量的范围计算行号和列号
+    pub fn from_byte_range(text: &str, range: std::ops::Range<usize>) -> Self {
+        return TextSpan {
+            start: TextPosition::from_byte_offset(text, range.start),
+            end: TextPosition::from_byte_offset(text, range.end),
+        };
+    }
+}
+
+impl TextPosition {
+    pub fn from_byte_offset(text: &str, offset: usize) -> Self {
+        let offset = offset.min(text.len());
+        let before = text.get(..offset).unwrap_or(text);
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        return TextPosition {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        };
+    }
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(span) = &self.span {
+            write!(f, "{}:{}: ", span.start.line, span.start.column)?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, "{key}: ")?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(hint) = &self.hint {
+            write!(f, " ({hint})")?;
+        }
+        return Ok(());
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        return Error {
+            kind,
+            message: message.into(),
+            path: None,
+            detail: None,
+            issues: vec![],
+        };
+    }
+
+    pub fn io(message: impl Into<String>, e: std::io::Error) -> Self {
+        return Error::new(ErrorKind::Io, message).with_detail(e);
+    }
+
+    pub fn sqlite(message: impl Into<String>, e: rusqlite::Error) -> Self {
+        return Error::new(ErrorKind::Sqlite, message).with_detail(e);
+    }
+
+    pub fn invalid_dictionary(message: impl Into<String>) -> Self {
+        return Error::new(ErrorKind::InvalidDictionary, message);
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        return Error::new(ErrorKind::Internal, message);
+    }
+
+    /// 设置相关的文件路径，已有路径时不覆盖（保留最具体的路径）
+    pub fn with_path(mut self, path: impl AsRef<Path>) -> Self {
+        if self.path.is_none() {
+            self.path = Some(path.as_ref().to_string_lossy().into_owned());
+        }
+        return self;
+    }
+
+    pub fn with_detail(mut self, detail: impl std::fmt::Display) -> Self {
+        self.detail = Some(detail.to_string());
+        return self;
+    }
+
+    pub fn with_issues(mut self, issues: Vec<Issue>) -> Self {
+        self.issues = issues;
+        return self;
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(path) = &self.path {
+            write!(f, " ({path})")?;
+        }
+        if let Some(detail) = &self.detail {
+            write!(f, ": {detail}")?;
+        }
+        for issue in &self.issues {
+            write!(f, "\n  {issue}")?;
+        }
+        return Ok(());
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,145 @@
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+use rusqlite::{Connection, OptionalExtension};
+
+use super::dict;
+use super::error::{Error, Result};
+
+/// 词频表的表名，可以放在 dict.db 中，也可以放在 dict.db 旁边的 frequency.db 中
+pub const FREQUENCY_TABLE: &str = "frequency";
+
+/// 词频数据库的文件名，仅当 dict.db 中没有词频表时使用
+pub const FREQUENCY_DB_FILE: &str = "frequency.db";
+
+/// 单词的词频与难度
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordLevel {
+    /// 词频表中的词头（可能是查询词的原型）
+    word: String,
+    /// 词频排名，越小越常用
+    rank: Option<u32>,
+    /// CEFR 等级，如 "A1"、"B2"
+    cefr: Option<String>,
+    /// 考试词表标签，如 "cet4"、"cet6"、"ielts"、"toefl"
+    tags: Vec<String>,
+}
+
+/// 词频数据源
+///
+/// 词频表的结构为 `frequency(word, rank, cefr, tags)`，其中 `tags` 为以空格或逗号分隔的考试词表标签，
+/// `rank`、`cefr`、`tags` 均可以为 null。
+pub struct FrequencySource {
+    conn: Mutex<Connection>,
+}
+
+impl FrequencySource {
+    /// 打开词频数据源，优先使用 dict.db 中的词频表，其次是 dict.db 旁边的 frequency.db，都没有时返回 `None`
+    pub fn open(dict_path: impl AsRef<Path>) -> Result<Option<Self>> {
+        fn inner(dict_path: &Path) -> Result<Option<FrequencySource>> {
+            let conn = dict::open_connection(dict_path)?;
+            if has_frequency_table(&conn, dict_path)? {
+                return FrequencySource::new(dict_path, conn).map(Some);
+            }
+            let sidecar_path = match dict_path.parent() {
+                Some(dir) => dir.join(FREQUENCY_DB_FILE),
+                None => return Ok(None),
+            };
+            if !sidecar_path.is_file() {
+                return Ok(None);
+            }
+            let conn = dict::open_connection(&sidecar_path)?;
+            if !has_frequency_table(&conn, &sidecar_path)? {
+                return Err(Error::invalid_dictionary(format!(
+                    "{FREQUENCY_DB_FILE} does not contain table {FREQUENCY_TABLE}"
+                ))
+                .with_path(&sidecar_path));
+            }
+            return FrequencySource::new(&sidecar_path, conn).map(Some);
+        }
+        return inner(dict_path.as_ref());
+    }
+
+    fn new(path: &Path, conn: Connection) -> Result<Self> {
+        // 预编译一次，使缺少列的错误在加载时就能发现
+        conn.prepare_cached(LEVEL_SQL).map_err(|e| {
+            Error::invalid_dictionary(format!(
+                "table {FREQUENCY_TABLE} must have columns word, rank, cefr and tags"
+            ))
+            .with_path(path)
+            .with_detail(e)
+        })?;
+        return Ok(FrequencySource {
+            conn: Mutex::new(conn),
+        });
+    }
+
+    /// 查询单词的词频与难度（不区分大小写），词频表中没有该单词时返回 `None`
+    pub fn level(&self, word: &str) -> Result<Option<WordLevel>> {
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut stmt = conn
+            .prepare_cached(LEVEL_SQL)
+            .map_err(|e| Error::sqlite("failed to prepare SQL statement for word level", e))?;
+        let row = stmt
+            .query_row([word], |row| {
+                let rank: Option<i64> = row.get("rank")?;
+                let cefr: Option<String> = row.get("cefr")?;
+                let tags: Option<String> = row.get("tags")?;
+                return Ok((row.get::<_, String>("word")?, rank, cefr, tags));
+            })
+            .optional()
+            .map_err(|e| Error::sqlite("failed to query word level", e))?;
+        let Some((word, rank, cefr, tags)) = row else {
+            return Ok(None);
+        };
+        return Ok(Some(WordLevel {
+            word,
+            rank: rank.and_then(|rank| u32::try_from(rank).ok()),
+            cefr: cefr
+                .map(|cefr| cefr.trim().to_uppercase())
+                .filter(|cefr| !cefr.is_empty()),
+            tags: tags.as_deref().map(parse_tags).unwrap_or_default(),
+        }));
+    }
+}
+
+/// 查询单词的词频与难度，单词本身不在词频表中时使用其原型
+pub fn word_level(
+    source: &FrequencySource,
+    word: &str,
+    base: Option<&str>,
+) -> Result<Option<WordLevel>> {
+    if let Some(level) = source.level(word)? {
+        return Ok(Some(level));
+    }
+    return match base {
+        Some(base) => source.level(base),
+        None => Ok(None),
+    };
+}
+
+const LEVEL_SQL: &str =
+    "select word, rank, cefr, tags from frequency where word = ?1 collate nocase limit 1";
+
+fn has_frequency_table(conn: &Connection, path: &Path) -> Result<bool> {
+    return conn
+        .query_row(
+            "select exists(select 1 from sqlite_master where type = 'table' and name = ?1)",
+            [FREQUENCY_TABLE],
+            |row| row.get(0),
+        )
+        .map_err(|e| Error::sqlite("failed to detect frequency table", e).with_path(path));
+}
+
+/// 解析考试词表标签，统一为小写并去掉其中的连字符，如 "CET-4" 解析为 "cet4"
+fn parse_tags(tags: &str) -> Vec<String> {
+    let mut result: Vec<String> = vec![];
+    for tag in tags.split(|c: char| c.is_whitespace() || c == ',' || c == ';') {
+        let tag = tag.to_lowercase().replace('-', "");
+        if !tag.is_empty() && !result.contains(&tag) {
+            result.push(tag);
+        }
+    }
+    return result;
+}
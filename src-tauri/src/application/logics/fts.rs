@@ -0,0 +1,279 @@
+use std::path::Path;
+
+use rusqlite::{Connection, OptionalExtension};
+
+use super::dict::{self, CollinsItem, OxfordItem};
+use super::error::{Error, Result};
+
+/// 释义全文索引的表名
+///
+/// 若 dict.db 中已经带有同名的 FTS5 表，则直接使用；否则在用户缓存目录中建立索引。
+/// 该表的结构为 `definitions_fts(source, ref, en_def, cn_def)`，其中 `source` 为
+/// `collins` 或 `oxford`，`ref` 为原表中的 rowid，`cn_def` 中的汉字需以空格分隔。
+const INDEX_TABLE: &str = "definitions_fts";
+
+/// 根据释义反查到的词条
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "dictionary", rename_all = "camelCase")]
+pub enum DefinitionHit {
+    Collins {
+        /// 相关度，越大越相关
+        score: f64,
+        item: CollinsItem,
+    },
+    Oxford {
+        score: f64,
+        item: OxfordItem,
+    },
+}
+
+/// dict.db 中是否自带释义全文索引
+pub fn has_builtin_index(dict_conn: &Connection) -> Result<bool> {
+    let exists = dict_conn
+        .query_row(
+            "select 1 from sqlite_master where name = ?1",
+            [INDEX_TABLE],
+            |_| Ok(()),
+        )
+        .optional()
+        .map_err(|e| Error::sqlite("failed to detect full-text index in dict.db", e))?
+        .is_some();
+    return Ok(exists);
+}
+
+/// 打开用户缓存目录中的释义索引，若索引不存在或与当前 dict.db 不匹配，则重新建立
+pub fn open_index(
+    index_path: impl AsRef<Path>,
+    dict_path: impl AsRef<Path>,
+    dict_conn: &Connection,
+) -> Result<Connection> {
+    fn inner(index_path: &Path, dict_path: &Path, dict_conn: &Connection) -> Result<Connection> {
+        if let Some(index_dir) = index_path.parent() {
+            std::fs::create_dir_all(index_dir)
+                .map_err(|e| Error::io("failed to create directory", e).with_path(index_dir))?;
+        }
+        let mut index_conn = Connection::open(index_path).map_err(|e| {
+            Error::sqlite("failed to open definition index", e).with_path(index_path)
+        })?;
+        index_conn
+            .execute_batch(
+                "create table if not exists meta (key text primary key, value text not null)",
+            )
+            .map_err(|e| Error::sqlite("failed to create meta table in definition index", e))?;
+        let fingerprint = dict::dict_fingerprint(dict_path)?;
+        let indexed: Option<String> = index_conn
+            .query_row(
+                "select value from meta where key = 'dict-fingerprint'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| Error::sqlite("failed to read definition index metadata", e))?;
+        if indexed.as_deref() != Some(fingerprint.as_str()) {
+            build_index(&mut index_conn, dict_conn, &fingerprint)?;
+        }
+        return Ok(index_conn);
+    }
+    return inner(index_path.as_ref(), dict_path.as_ref(), dict_conn);
+}
+
+fn build_index(
+    index_conn: &mut Connection,
+    dict_conn: &Connection,
+    fingerprint: &str,
+) -> Result<()> {
+    let tx = index_conn
+        .transaction()
+        .map_err(|e| Error::sqlite("failed to begin transaction for definition index", e))?;
+    tx.execute_batch(&format!(
+        "drop table if exists {INDEX_TABLE};
+        create virtual table {INDEX_TABLE} using fts5(
+            source unindexed,
+            ref unindexed,
+            en_def,
+            cn_def,
+            tokenize = 'porter unicode61 remove_diacritics 2'
+        );"
+    ))
+    .map_err(|e| Error::sqlite("failed to create definition index table", e))?;
+    {
+        let mut insert = tx
+            .prepare(&format!(
+                "insert into {INDEX_TABLE} (source, ref, en_def, cn_def) values (?1, ?2, ?3, ?4)"
+            ))
+            .map_err(|e| {
+                Error::sqlite("failed to prepare SQL statement for definition index", e)
+            })?;
+        for source in ["collins", "oxford"] {
+            let mut stmt = dict_conn
+                .prepare(&format!("select rowid, enDef, cnDef from {source}"))
+                .map_err(|e| {
+                    Error::sqlite(
+                        format!("failed to prepare SQL statement for {source} definitions"),
+                        e,
+                    )
+                })?;
+            let mut rows = stmt
+                .query([])
+                .map_err(|e| Error::sqlite(format!("failed to query {source} definitions"), e))?;
+            while let Some(row) = rows.next().map_err(|e| {
+                Error::sqlite(
+                    format!("failed to get next row from {source} definitions"),
+                    e,
+                )
+            })? {
+                let rowid: i64 = row
+                    .get(0)
+                    .map_err(|e| Error::sqlite("failed to get rowid", e))?;
+                let en_def: Option<String> = row
+                    .get(1)
+                    .map_err(|e| Error::sqlite("failed to get enDef", e))?;
+                let cn_def: Option<String> = row
+                    .get(2)
+                    .map_err(|e| Error::sqlite("failed to get cnDef", e))?;
+                if en_def.is_none() && cn_def.is_none() {
+                    continue;
+                }
+                insert
+                    .execute(rusqlite::params![
+                        source,
+                        rowid,
+                        en_def,
+                        cn_def.as_deref().map(segment_cjk)
+                    ])
+                    .map_err(|e| Error::sqlite("failed to insert into definition index", e))?;
+            }
+        }
+    }
+    tx.execute(
+        "insert or replace into meta (key, value) values ('dict-fingerprint', ?1)",
+        [fingerprint],
+    )
+    .map_err(|e| Error::sqlite("failed to write definition index metadata", e))?;
+    tx.commit()
+        .map_err(|e| Error::sqlite("failed to commit definition index", e))?;
+    return Ok(());
+}
+
+/// 按释义搜索词条，中文在 `cnDef` 中匹配，英文在 `enDef` 中匹配，结果按相关度排序
+///
+/// `index_conn` 为释义索引所在的数据库，可以与 `dict_conn` 是同一个连接。
+pub fn search_definitions(
+    index_conn: &Connection,
+    dict_conn: &Connection,
+    query: impl AsRef<str>,
+    limit: usize,
+) -> Result<Vec<DefinitionHit>> {
+    fn inner(
+        index_conn: &Connection,
+        dict_conn: &Connection,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<DefinitionHit>> {
+        let Some(expression) = match_expression(query) else {
+            return Ok(vec![]);
+        };
+        let mut stmt = index_conn
+            .prepare_cached(&format!(
+                "select source, ref, bm25({INDEX_TABLE}) as relevance from {INDEX_TABLE}
+                where {INDEX_TABLE} match ?1 order by relevance limit ?2"
+            ))
+            .map_err(|e| {
+                Error::sqlite("failed to prepare SQL statement for definition search", e)
+            })?;
+        let mut rows = stmt
+            .query(rusqlite::params![expression, limit as i64])
+            .map_err(|e| Error::sqlite("failed to query definition index", e))?;
+        let mut hits = vec![];
+        while let Some(row) = rows
+            .next()
+            .map_err(|e| Error::sqlite("failed to get next row from definition search result", e))?
+        {
+            let source: String = row
+                .get("source")
+                .map_err(|e| Error::sqlite("failed to get source", e))?;
+            let rowid: i64 = row
+                .get("ref")
+                .map_err(|e| Error::sqlite("failed to get ref", e))?;
+            let relevance: f64 = row
+                .get("relevance")
+                .map_err(|e| Error::sqlite("failed to get relevance", e))?;
+            // bm25 的值越小越相关，取相反数使 score 越大越相关
+            let score = -relevance;
+            let hit = match source.as_str() {
+                "collins" => dict::get_collins_by_rowid(dict_conn, rowid)?
+                    .map(|item| DefinitionHit::Collins { score, item }),
+                "oxford" => dict::get_oxford_by_rowid(dict_conn, rowid)?
+                    .map(|item| DefinitionHit::Oxford { score, item }),
+                _ => None,
+            };
+            if let Some(hit) = hit {
+                hits.push(hit);
+            }
+        }
+        return Ok(hits);
+    }
+    return inner(index_conn, dict_conn, query.as_ref(), limit);
+}
+
+/// 将用户输入转换为 FTS5 查询表达式
+///
+/// 连续的汉字作为一个短语在 `cn_def` 中匹配，其余单词在 `en_def` 中匹配，各部分之间为 AND 关系。
+fn match_expression(query: &str) -> Option<String> {
+    fn quote(term: &str) -> String {
+        return format!("\"{}\"", term.replace('"', "\"\""));
+    }
+    let mut parts = vec![];
+    let mut segment = String::new();
+    let mut segment_is_cjk = false;
+    // 末尾追加一个分隔符，以便输出最后一段
+    for c in query.chars().chain(std::iter::once(' ')) {
+        let kind = if is_cjk(c) {
+            Some(true)
+        } else if c.is_alphanumeric() {
+            Some(false)
+        } else {
+            None
+        };
+        if !segment.is_empty() && kind != Some(segment_is_cjk) {
+            if segment_is_cjk {
+                parts.push(format!("cn_def : {}", quote(&segment_cjk(&segment))));
+            } else {
+                parts.push(format!("en_def : {}", quote(&segment)));
+            }
+            segment.clear();
+        }
+        if let Some(is_cjk) = kind {
+            segment_is_cjk = is_cjk;
+            segment.push(c);
+        }
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    return Some(parts.join(" AND "));
+}
+
+/// 在汉字之间插入空格，使 unicode61 分词器把每个汉字作为一个词
+fn segment_cjk(text: &str) -> String {
+    let mut segmented = String::with_capacity(text.len() * 2);
+    for c in text.chars() {
+        if is_cjk(c) {
+            segmented.push(' ');
+            segmented.push(c);
+            segmented.push(' ');
+        } else {
+            segmented.push(c);
+        }
+    }
+    return segmented;
+}
+
+fn is_cjk(c: char) -> bool {
+    return matches!(c,
+        '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FA1F}'
+    );
+}
@@ -0,0 +1,336 @@
+//! 将词典中的词性和语法标注（collins 的 sense，oxford 的 sense 和 ext）解析为结构化的数据
+//!
+//! 支持 collins 的大写代码（N-COUNT、V-T、ADJ-GRADED 等）、oxford 的词性全称和方括号、圆括号中的标签
+//! （[countable]、[transitive]、(informal)、(British English) 等），以及 n.、vt. 等常见缩写。
+//! 无法识别的部分放在 `patterns` 中，原始文本保留在 `raw` 中。
+
+/// 词性
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PartOfSpeech {
+    Noun,
+    Verb,
+    PhrasalVerb,
+    ModalVerb,
+    AuxiliaryVerb,
+    Adjective,
+    Adverb,
+    Pronoun,
+    Preposition,
+    Conjunction,
+    Determiner,
+    /// 数词（包括基数词、序数词）
+    Number,
+    /// 量词（collins 的 QUANT，如 a lot of）
+    Quantifier,
+    Exclamation,
+    /// 惯用语、固定短语（collins 的 PHRASE、CONVENTION，oxford 的 idiom）
+    Phrase,
+    Prefix,
+    Suffix,
+    /// 构词成分（collins 的 COMB，oxford 的 combining form）
+    CombiningForm,
+    Abbreviation,
+    Symbol,
+}
+
+/// 名词的可数性
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Countability {
+    Countable,
+    Uncountable,
+    /// 既可数又不可数（collins 的 N-VAR，oxford 的 [countable, uncountable]）
+    Variable,
+    /// 只用单数
+    Singular,
+    /// 只用复数
+    Plural,
+    /// 专有名词
+    Proper,
+}
+
+/// 动词的及物性
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transitivity {
+    Transitive,
+    Intransitive,
+    /// 既可及物又可不及物（包括 collins 的作格动词 V-ERG）
+    Both,
+    /// 系动词
+    Linking,
+}
+
+/// 解析后的词性和语法标注
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Grammar {
+    /// 词性，标注中有多个词性时取第一个
+    pub pos: Option<PartOfSpeech>,
+    pub countability: Option<Countability>,
+    pub transitivity: Option<Transitivity>,
+    /// 语体标签，统一为小写（如 informal、formal、literary、old-fashioned）
+    pub registers: Vec<String>,
+    /// 地区标签，统一为缩写（如 BrE、AmE、NAmE）
+    pub regions: Vec<String>,
+    /// 无法识别的标注（如语法搭配 ~ sth、usually passive）
+    pub patterns: Vec<String>,
+    /// 原始文本，sense 和 ext 之间以空格连接
+    pub raw: String,
+}
+
+/// 一个标签的含义
+enum Label {
+    Pos(PartOfSpeech, Option<Countability>, Option<Transitivity>),
+    Countability(Countability),
+    Transitivity(Transitivity),
+    Register(&'static str),
+    Region(&'static str),
+}
+
+/// 语体标签，键和值都是小写
+const REGISTERS: &[(&str, &str)] = &[
+    ("informal", "informal"),
+    ("formal", "formal"),
+    ("slang", "slang"),
+    ("literary", "literary"),
+    ("old-fashioned", "old-fashioned"),
+    ("old use", "old-fashioned"),
+    ("dated", "old-fashioned"),
+    ("archaic", "archaic"),
+    ("offensive", "offensive"),
+    ("rude", "offensive"),
+    ("taboo", "taboo"),
+    ("vulgar", "taboo"),
+    ("humorous", "humorous"),
+    ("ironic", "ironic"),
+    ("disapproving", "disapproving"),
+    ("pragmatics", "pragmatics"),
+    ("approving", "approving"),
+    ("technical", "technical"),
+    ("specialist", "technical"),
+    ("written", "written"),
+    ("spoken", "spoken"),
+    ("figurative", "figurative"),
+    ("journalism", "journalism"),
+    ("dialect", "dialect"),
+    ("euphemistic", "euphemistic"),
+    ("rare", "rare"),
+];
+
+/// 地区标签，键是小写，值是统一的缩写
+const REGIONS: &[(&str, &str)] = &[
+    ("british english", "BrE"),
+    ("british", "BrE"),
+    ("brit", "BrE"),
+    ("bre", "BrE"),
+    ("american english", "AmE"),
+    ("american", "AmE"),
+    ("am", "AmE"),
+    ("ame", "AmE"),
+    ("us", "AmE"),
+    ("north american english", "NAmE"),
+    ("name", "NAmE"),
+    ("australian english", "AusE"),
+    ("austral", "AusE"),
+    ("scottish english", "ScotE"),
+    ("scottish", "ScotE"),
+    ("irish english", "IrE"),
+    ("indian english", "IndE"),
+    ("canadian english", "CanE"),
+    ("south african english", "SAfrE"),
+    ("new zealand english", "NZE"),
+];
+
+/// 地区标签前的修饰词（如 especially British English），解析时去掉
+const QUALIFIERS: &[&str] = &[
+    "especially ",
+    "esp. ",
+    "esp ",
+    "mainly ",
+    "chiefly ",
+    "mostly ",
+];
+
+/// 解析 sense 和 ext，两者都为空时返回 `None`
+pub fn parse_grammar(sense: Option<&str>, ext: Option<&str>) -> Option<Grammar> {
+    let parts: Vec<&str> = [sense, ext]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.is_empty() {
+        return None;
+    }
+    let mut grammar = Grammar {
+        raw: parts.join(" "),
+        ..Default::default()
+    };
+    for part in parts {
+        for label in split_labels(part) {
+            grammar.apply(label);
+        }
+    }
+    return Some(grammar);
+}
+
+/// 按括号和分隔符拆分标签，如 `[countable, uncountable] (informal)` 拆分为三个标签
+fn split_labels(text: &str) -> impl Iterator<Item = &str> {
+    return text
+        .split(['[', ']', '(', ')', ';', ',', '/', '|'])
+        .map(str::trim)
+        .filter(|label| !label.is_empty());
+}
+
+impl Grammar {
+    fn apply(&mut self, label: &str) {
+        if let Some(parsed) = parse_label(label) {
+            self.apply_parsed(parsed);
+            return;
+        }
+        // collins 的代码后可能跟着语法搭配（如 ADJ-GRADED usu ADJ n），只识别第一个词
+        if let Some((first, rest)) = label.split_once(char::is_whitespace) {
+            if let Some(parsed) = parse_label(first) {
+                self.apply_parsed(parsed);
+                self.patterns.push(rest.trim().to_string());
+                return;
+            }
+        }
+        self.patterns.push(label.to_string());
+    }
+
+    fn apply_parsed(&mut self, label: Label) {
+        match label {
+            Label::Pos(pos, countability, transitivity) => {
+                if self.pos.is_none() {
+                    self.pos = Some(pos);
+                }
+                if let Some(countability) = countability {
+                    self.merge_countability(countability);
+                }
+                if let Some(transitivity) = transitivity {
+                    self.merge_transitivity(transitivity);
+                }
+            }
+            Label::Countability(countability) => {
+                self.merge_countability(countability);
+            }
+            Label::Transitivity(transitivity) => {
+                self.merge_transitivity(transitivity);
+            }
+            Label::Register(register) => {
+                if !self.registers.iter().any(|r| r == register) {
+                    self.registers.push(register.to_string());
+                }
+            }
+            Label::Region(region) => {
+                if !self.regions.iter().any(|r| r == region) {
+                    self.regions.push(region.to_string());
+                }
+            }
+        }
+    }
+
+    /// 同时标注了可数和不可数时合并为 `Variable`，其他冲突的标注保留第一个
+    fn merge_countability(&mut self, countability: Countability) {
+        use Countability::*;
+        self.countability = match (self.countability, countability) {
+            (None, new) => Some(new),
+            (Some(Countable), Uncountable) | (Some(Uncountable), Countable) => Some(Variable),
+            (old, _) => old,
+        };
+    }
+
+    /// 同时标注了及物和不及物时合并为 `Both`，其他冲突的标注保留第一个
+    fn merge_transitivity(&mut self, transitivity: Transitivity) {
+        use Transitivity::*;
+        self.transitivity = match (self.transitivity, transitivity) {
+            (None, new) => Some(new),
+            (Some(Transitive), Intransitive) | (Some(Intransitive), Transitive) => Some(Both),
+            (old, _) => old,
+        };
+    }
+}
+
+/// 识别一个标签，不区分大小写，忽略末尾的句点和多余的空白
+fn parse_label(label: &str) -> Option<Label> {
+    let key = label
+        .trim()
+        .trim_end_matches('.')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    if let Some(label) = parse_pos(&key) {
+        return Some(label);
+    }
+    use Countability::*;
+    use Transitivity::*;
+    let label = match key.as_str() {
+        "countable" | "c" => Label::Countability(Countable),
+        "uncountable" | "u" => Label::Countability(Uncountable),
+        "singular" | "sing" => Label::Countability(Singular),
+        "plural" | "pl" => Label::Countability(Plural),
+        "transitive" | "t" => Label::Transitivity(Transitive),
+        "intransitive" | "i" => Label::Transitivity(Intransitive),
+        "linking verb" | "linking" => Label::Transitivity(Linking),
+        _ => {
+            if let Some((_, register)) = REGISTERS.iter().find(|(k, _)| *k == key) {
+                return Some(Label::Register(register));
+            }
+            let region = QUALIFIERS
+                .iter()
+                .find_map(|qualifier| key.strip_prefix(qualifier))
+                .unwrap_or(&key);
+            let (_, region) = REGIONS.iter().find(|(k, _)| *k == region)?;
+            Label::Region(region)
+        }
+    };
+    return Some(label);
+}
+
+/// 识别词性，包括 collins 的代码、oxford 的全称和常见缩写
+fn parse_pos(key: &str) -> Option<Label> {
+    use Countability::*;
+    use PartOfSpeech::*;
+    use Transitivity::*;
+    let (pos, countability, transitivity) = match key {
+        "noun" | "n" => (Noun, None, None),
+        "n-count" => (Noun, Some(Countable), None),
+        "n-uncount" | "n-mass" => (Noun, Some(Uncountable), None),
+        "n-var" => (Noun, Some(Variable), None),
+        "n-sing" => (Noun, Some(Singular), None),
+        "n-plural" => (Noun, Some(Plural), None),
+        "n-proper" | "n-proper-plural" | "n-title" => (Noun, Some(Proper), None),
+        "n-voc" | "n-family" | "n-in-names" => (Noun, None, None),
+        "verb" | "v" => (Verb, None, None),
+        "v-t" | "vt" => (Verb, None, Some(Transitive)),
+        "v-i" | "vi" => (Verb, None, Some(Intransitive)),
+        "v-erg" | "v-recip" | "v-recip-erg" => (Verb, None, Some(Both)),
+        "v-link" => (Verb, None, Some(Linking)),
+        "v-passive" => (Verb, None, Some(Transitive)),
+        "phrasal verb" | "phr-v" => (PhrasalVerb, None, None),
+        "phr-erg" => (PhrasalVerb, None, Some(Both)),
+        "modal" | "modal verb" => (ModalVerb, None, None),
+        "aux" | "auxiliary verb" => (AuxiliaryVerb, None, None),
+        "adjective" | "adj" | "adj-graded" | "colour" | "color" => (Adjective, None, None),
+        "adverb" | "adv" | "adv-graded" => (Adverb, None, None),
+        "pronoun" | "pron" => (Pronoun, None, None),
+        "preposition" | "prep" | "phr-prep" => (Preposition, None, None),
+        "conjunction" | "conj" | "phr-conj-subord" | "phr-conj-coord" => (Conjunction, None, None),
+        "determiner" | "det" | "art" | "article" | "predet" => (Determiner, None, None),
+        "number" | "num" | "ord" | "ordinal number" | "cardinal number" => (Number, None, None),
+        "quant" | "quantifier" => (Quantifier, None, None),
+        "exclamation" | "exclam" | "interjection" | "int" | "interj" => (Exclamation, None, None),
+        "phrase" | "convention" | "idiom" => (Phrase, None, None),
+        "prefix" => (Prefix, None, None),
+        "suffix" => (Suffix, None, None),
+        "comb" | "combining form" => (CombiningForm, None, None),
+        "abbreviation" | "abbr" => (Abbreviation, None, None),
+        "symbol" => (Symbol, None, None),
+        _ => return None,
+    };
+    return Some(Label::Pos(pos, countability, transitivity));
+}
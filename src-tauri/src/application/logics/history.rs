@@ -0,0 +1,106 @@
+use rusqlite::Connection;
+
+use super::error::{Error, Result};
+use super::userdb::now_millis;
+
+/// 一次查询记录
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    id: i64,
+    /// 查询时间，Unix 时间戳（毫秒）
+    time: i64,
+    /// 查询的单词（句子中的原始形式）
+    word: String,
+    /// 单词的原型，单词本身即为原型或无法确定原型时为 `None`
+    base: Option<String>,
+    /// 单词所在的句子
+    sentence: Option<String>,
+}
+
+/// 按原型统计的查询次数
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LookupCount {
+    /// 单词的原型（没有原型时为单词本身的小写形式）
+    word: String,
+    count: u32,
+    /// 最近一次查询的时间，Unix 时间戳（毫秒）
+    last_time: i64,
+}
+
+/// 记录一次查询
+pub fn record_lookup(
+    conn: &Connection,
+    word: &str,
+    base: Option<&str>,
+    sentence: Option<&str>,
+) -> Result<HistoryEntry> {
+    let time = now_millis();
+    let sentence = sentence.map(str::trim).filter(|s| !s.is_empty());
+    conn.prepare_cached("insert into history (time, word, base, sentence) values (?1, ?2, ?3, ?4)")
+        .and_then(|mut stmt| stmt.execute((time, word, base, sentence)))
+        .map_err(|e| Error::sqlite("failed to record lookup history", e))?;
+    return Ok(HistoryEntry {
+        id: conn.last_insert_rowid(),
+        time,
+        word: word.to_string(),
+        base: base.map(str::to_string),
+        sentence: sentence.map(str::to_string),
+    });
+}
+
+/// 最近的查询记录，按时间从新到旧排列
+pub fn recent_lookups(conn: &Connection, limit: usize) -> Result<Vec<HistoryEntry>> {
+    let mut stmt = conn
+        .prepare_cached(
+            "select id, time, word, base, sentence from history order by time desc, id desc limit ?1",
+        )
+        .map_err(|e| Error::sqlite("failed to prepare SQL statement for recent lookups", e))?;
+    let entries = stmt
+        .query_map([limit as i64], |row| {
+            return Ok(HistoryEntry {
+                id: row.get("id")?,
+                time: row.get("time")?,
+                word: row.get("word")?,
+                base: row.get("base")?,
+                sentence: row.get("sentence")?,
+            });
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| Error::sqlite("failed to query recent lookups", e))?;
+    return Ok(entries);
+}
+
+/// 查询次数最多的单词（按原型合并），次数相同时最近查询的排在前面
+pub fn most_looked_up(conn: &Connection, limit: usize) -> Result<Vec<LookupCount>> {
+    let mut stmt = conn
+        .prepare_cached(
+            "select lower(coalesce(base, word)) as key, count(*) as count, max(time) as last_time
+            from history group by key order by count desc, last_time desc limit ?1",
+        )
+        .map_err(|e| {
+            Error::sqlite(
+                "failed to prepare SQL statement for most looked up words",
+                e,
+            )
+        })?;
+    let counts = stmt
+        .query_map([limit as i64], |row| {
+            return Ok(LookupCount {
+                word: row.get("key")?,
+                count: row.get("count")?,
+                last_time: row.get("last_time")?,
+            });
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| Error::sqlite("failed to query most looked up words", e))?;
+    return Ok(counts);
+}
+
+/// 清空查询记录
+pub fn clear_history(conn: &Connection) -> Result<()> {
+    conn.execute("delete from history", [])
+        .map_err(|e| Error::sqlite("failed to clear lookup history", e))?;
+    return Ok(());
+}
@@ -0,0 +1,167 @@
+use super::error::{Error, Result};
+
+/// LZO1X 解压缩（MDict 早期版本的压缩格式）
+///
+/// 算法与 `lzo1x_decompress_safe` 相同，所有读写都会检查边界，损坏的数据只会返回错误。
+/// `size` 为声明的解压后大小，输出超过该大小时返回错误；`capacity` 仅用于预分配内存。
+pub fn decompress(input: &[u8], size: usize, capacity: usize) -> Result<Vec<u8>> {
+    let mut decoder = Decoder {
+        input,
+        ip: 0,
+        output: Vec::with_capacity(capacity.min(size)),
+        limit: size,
+    };
+    decoder.run()?;
+    return Ok(decoder.output);
+}
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    ip: usize,
+    output: Vec<u8>,
+    /// 输出的最大长度
+    limit: usize,
+}
+
+/// 0x0800，M2 匹配的最大距离
+const M2_MAX_OFFSET: usize = 0x0800;
+
+impl Decoder<'_> {
+    fn run(&mut self) -> Result<()> {
+        // 上一条指令之后附带的字面量个数，4 表示上一条指令是一段较长的字面量
+        let mut state;
+        let first = self.peek()? as usize;
+        if first > 17 {
+            self.ip += 1;
+            let length = first - 17;
+            self.copy_literals(length)?;
+            state = if length < 4 { length } else { 4 };
+        } else {
+            state = 0;
+        }
+        loop {
+            let t = self.next_byte()? as usize;
+            let (distance, length, next);
+            if t < 16 {
+                if state == 0 {
+                    // 一段字面量
+                    let mut length = t;
+                    if length == 0 {
+                        length = 15 + self.read_extended_length()?;
+                    }
+                    self.copy_literals(length + 3)?;
+                    state = 4;
+                    continue;
+                }
+                let low = self.next_byte()? as usize;
+                next = t & 3;
+                if state != 4 {
+                    distance = 1 + (t >> 2) + (low << 2);
+                    length = 2;
+                } else {
+                    distance = 1 + M2_MAX_OFFSET + (t >> 2) + (low << 2);
+                    length = 3;
+                }
+            } else if t >= 64 {
+                let low = self.next_byte()? as usize;
+                next = t & 3;
+                distance = 1 + ((t >> 2) & 7) + (low << 3);
+                length = (t >> 5) + 1;
+            } else if t >= 32 {
+                length = match t & 31 {
+                    0 => 31 + self.read_extended_length()? + 2,
+                    n => n + 2,
+                };
+                let le16 = self.read_le16()?;
+                next = le16 & 3;
+                distance = 1 + (le16 >> 2);
+            } else {
+                let high = (t & 8) << 11;
+                length = match t & 7 {
+                    0 => 7 + self.read_extended_length()? + 2,
+                    n => n + 2,
+                };
+                let le16 = self.read_le16()?;
+                next = le16 & 3;
+                let offset = high + (le16 >> 2);
+                if offset == 0 {
+                    // 结束标记
+                    return Ok(());
+                }
+                distance = offset + 0x4000;
+            }
+            self.copy_match(distance, length)?;
+            self.copy_literals(next)?;
+            state = next;
+        }
+    }
+
+    fn peek(&self) -> Result<u8> {
+        return self
+            .input
+            .get(self.ip)
+            .copied()
+            .ok_or_else(|| Error::invalid_dictionary("LZO data is truncated"));
+    }
+
+    fn next_byte(&mut self) -> Result<u8> {
+        let byte = self.peek()?;
+        self.ip += 1;
+        return Ok(byte);
+    }
+
+    fn read_le16(&mut self) -> Result<usize> {
+        let low = self.next_byte()? as usize;
+        let high = self.next_byte()? as usize;
+        return Ok(low | (high << 8));
+    }
+
+    /// 读取长度的扩展部分：每个 0 字节表示 255，最后一个非 0 字节为余数
+    fn read_extended_length(&mut self) -> Result<usize> {
+        let mut length = 0;
+        loop {
+            let byte = self.next_byte()?;
+            if byte != 0 {
+                return Ok(length + byte as usize);
+            }
+            length += 255;
+        }
+    }
+
+    /// 检查输出 `length` 字节后是否会超过声明的大小
+    fn check_output(&self, length: usize) -> Result<()> {
+        if length > self.limit - self.output.len() {
+            return Err(Error::invalid_dictionary(
+                "LZO data is larger than the declared size",
+            ));
+        }
+        return Ok(());
+    }
+
+    fn copy_literals(&mut self, length: usize) -> Result<()> {
+        self.check_output(length)?;
+        let literals = self
+            .input
+            .get(self.ip..self.ip + length)
+            .ok_or_else(|| Error::invalid_dictionary("LZO data is truncated"))?;
+        self.output.extend_from_slice(literals);
+        self.ip += length;
+        return Ok(());
+    }
+
+    /// 复制已输出的数据，源与目标可以重叠
+    fn copy_match(&mut self, distance: usize, length: usize) -> Result<()> {
+        if distance > self.output.len() {
+            return Err(Error::invalid_dictionary(
+                "LZO data refers to a position before the start of the output",
+            ));
+        }
+        self.check_output(length)?;
+        let start = self.output.len() - distance;
+        for i in 0..length {
+            let byte = self.output[start + i];
+            self.output.push(byte);
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,740 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use encoding_rs::Encoding;
+
+use super::dict::{Dictionary, DictionaryEntry, DictionaryInfo};
+use super::error::{Error, Result};
+use super::lzo;
+
+/// 词条内容为 `@@@LINK=目标词头` 时表示跳转到另一个词条
+const LINK_PREFIX: &str = "@@@LINK=";
+
+/// 跳转的最大次数，防止循环跳转
+const MAX_LINK_DEPTH: usize = 5;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum MDictKind {
+    /// 词条文件
+    Mdx,
+    /// 资源文件（图片、音频、CSS 等）
+    Mdd,
+}
+
+/// MDict 文件头中的属性
+#[derive(Debug, Clone)]
+pub struct MDictHeader {
+    pub version: f32,
+    pub title: Option<String>,
+    pub encoding: &'static Encoding,
+    pub key_case_sensitive: bool,
+    /// 第 0 位表示词条区被加密（需要注册码），第 1 位表示索引信息被加密（可直接解密）
+    pub encrypted: u32,
+}
+
+/// 一个词头及其内容在解压后的词条区中的起始位置
+struct MDictKey {
+    text: String,
+    offset: u64,
+}
+
+/// 词条区中的一个压缩块
+struct RecordBlock {
+    /// 压缩块在文件中的位置
+    file_offset: u64,
+    compressed_size: u64,
+    /// 解压后的数据在整个词条区中的起始位置
+    offset: u64,
+    decompressed_size: u64,
+}
+
+/// MDict 文件（.mdx 或 .mdd）的读取器
+///
+/// 打开时读取文件头和全部词头，词条内容在查询时按块读取并解压。
+pub struct MDict {
+    path: PathBuf,
+    kind: MDictKind,
+    header: MDictHeader,
+    keys: Vec<MDictKey>,
+    /// 规范化后的词头到 `keys` 下标的映射
+    index: HashMap<String, Vec<usize>>,
+    blocks: Vec<RecordBlock>,
+    records_size: u64,
+    file: Mutex<File>,
+    /// 最近一次解压的词条块，相邻的查询通常落在同一个块中
+    last_block: Mutex<Option<(usize, Vec<u8>)>>,
+}
+
+impl MDict {
+    /// 打开 MDict 文件，按扩展名区分 .mdx 和 .mdd
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        fn inner(path: &Path) -> Result<MDict> {
+            let kind = match path.extension().and_then(|ext| ext.to_str()) {
+                Some(ext) if ext.eq_ignore_ascii_case("mdd") => MDictKind::Mdd,
+                _ => MDictKind::Mdx,
+            };
+            let mut file = File::open(path)
+                .map_err(|e| Error::io("failed to open MDict file", e).with_path(path))?;
+            return MDict::read(kind, &mut file)
+                .map(|(header, keys, blocks, records_size)| {
+                    let index = build_index(&keys, kind, header.key_case_sensitive);
+                    return MDict {
+                        path: path.to_path_buf(),
+                        kind,
+                        header,
+                        keys,
+                        index,
+                        blocks,
+                        records_size,
+                        file: Mutex::new(file),
+                        last_block: Mutex::new(None),
+                    };
+                })
+                .map_err(|e| e.with_path(path));
+        }
+        return inner(path.as_ref());
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn read(
+        kind: MDictKind,
+        file: &mut File,
+    ) -> Result<(MDictHeader, Vec<MDictKey>, Vec<RecordBlock>, u64)> {
+        // 文件头：4 字节长度（大端序），UTF-16LE 编码的 XML 标签，4 字节 Adler-32 校验和（小端序）
+        let header_size = u32::from_be_bytes(read_array(file)?) as usize;
+        let header_bytes = read_vec(file, header_size)?;
+        let checksum = u32::from_le_bytes(read_array(file)?);
+        if adler32(&header_bytes) != checksum {
+            return Err(Error::invalid_dictionary("header checksum mismatch"));
+        }
+        let header = parse_header(&header_bytes, kind)?;
+        if header.version >= 3.0 {
+            return Err(Error::invalid_dictionary(format!(
+                "MDict version {} is not supported",
+                header.version
+            )));
+        }
+        if header.encrypted & 1 != 0 {
+            return Err(Error::invalid_dictionary(
+                "the dictionary is encrypted and requires a registration key, which is not supported",
+            ));
+        }
+        let v2 = header.version >= 2.0;
+
+        // 词头区
+        let keys = {
+            let section_size = if v2 { 40 } else { 16 };
+            let section = read_vec(file, section_size)?;
+            if v2 {
+                let checksum = u32::from_be_bytes(read_array(file)?);
+                if adler32(&section) != checksum {
+                    return Err(Error::invalid_dictionary("key section checksum mismatch"));
+                }
+            }
+            let mut reader = ByteReader::new(&section, v2);
+            let num_blocks = reader.number()?;
+            let num_entries = reader.number()?;
+            // 解压后的索引信息大小，仅版本 2 中有
+            let info_decompressed_size = if v2 { Some(reader.number()?) } else { None };
+            let info_size = reader.number()?;
+            let blocks_size = reader.number()?;
+
+            let info = read_vec(file, to_usize(info_size)?)?;
+            let info = match info_decompressed_size {
+                Some(size) => {
+                    let mut info = info;
+                    if header.encrypted & 2 != 0 {
+                        decrypt_key_block_info(&mut info)?;
+                    }
+                    decompress_block(&info, to_usize(size)?)?
+                }
+                None => info,
+            };
+            let block_sizes = parse_key_block_info(&info, &header, num_blocks)?;
+            let blocks = read_vec(file, to_usize(blocks_size)?)?;
+            let mut keys = Vec::with_capacity(to_usize(num_entries)?.min(1 << 24));
+            let mut position = 0usize;
+            for (compressed_size, decompressed_size) in block_sizes {
+                let end = position
+                    .checked_add(to_usize(compressed_size)?)
+                    .ok_or_else(|| Error::invalid_dictionary("key block size overflows"))?;
+                let block = blocks
+                    .get(position..end)
+                    .ok_or_else(|| Error::invalid_dictionary("key block is out of range"))?;
+                let block = decompress_block(block, to_usize(decompressed_size)?)?;
+                parse_key_block(&block, &header, v2, &mut keys)?;
+                position = end;
+            }
+            keys
+        };
+
+        // 词条区
+        let section_size = if v2 { 32 } else { 16 };
+        let section = read_vec(file, section_size)?;
+        let mut reader = ByteReader::new(&section, v2);
+        let num_blocks = reader.number()?;
+        reader.number()?;
+        let info_size = reader.number()?;
+        reader.number()?;
+        let info = read_vec(file, to_usize(info_size)?)?;
+        let mut reader = ByteReader::new(&info, v2);
+        let mut file_offset = file
+            .stream_position()
+            .map_err(|e| Error::io("failed to get position in file", e))?;
+        let file_size = file
+            .metadata()
+            .map_err(|e| Error::io("failed to get file size", e))?
+            .len();
+        let mut offset = 0;
+        let mut blocks = Vec::with_capacity(to_usize(num_blocks)?.min(1 << 20));
+        for _ in 0..num_blocks {
+            let compressed_size = reader.number()?;
+            let decompressed_size = reader.number()?;
+            blocks.push(RecordBlock {
+                file_offset,
+                compressed_size,
+                offset,
+                decompressed_size,
+            });
+            file_offset = file_offset
+                .checked_add(compressed_size)
+                .filter(|&end| end <= file_size)
+                .ok_or_else(|| Error::invalid_dictionary("record block is out of range"))?;
+            offset = offset
+                .checked_add(decompressed_size)
+                .ok_or_else(|| Error::invalid_dictionary("record block size overflows"))?;
+        }
+        return Ok((header, keys, blocks, offset));
+    }
+
+    pub fn header(&self) -> &MDictHeader {
+        return &self.header;
+    }
+
+    /// 查询词头对应的所有原始内容（未解码）
+    pub fn lookup(&self, key: &str) -> Result<Vec<Vec<u8>>> {
+        let normalized = normalize_key(key, self.kind, self.header.key_case_sensitive);
+        let Some(indices) = self.index.get(&normalized) else {
+            return Ok(vec![]);
+        };
+        let mut records = vec![];
+        for &index in indices {
+            records.push(self.record(index)?);
+        }
+        return Ok(records);
+    }
+
+    /// 查询词头对应的所有词条（已按文件编码解码），`@@@LINK=` 跳转会被解析
+    pub fn lookup_text(&self, key: &str) -> Result<Vec<(String, String)>> {
+        let mut entries = vec![];
+        // 已读取的词条，同一词条只返回一次，也避免循环跳转
+        let mut visited = HashSet::new();
+        let mut pending = VecDeque::from([(key.to_string(), 0)]);
+        while let Some((key, depth)) = pending.pop_front() {
+            let normalized = normalize_key(&key, self.kind, self.header.key_case_sensitive);
+            let Some(indices) = self.index.get(&normalized) else {
+                continue;
+            };
+            for &index in indices {
+                if !visited.insert(index) {
+                    continue;
+                }
+                let text = self.decode(&self.record(index)?);
+                match text.trim().strip_prefix(LINK_PREFIX) {
+                    Some(target) if depth < MAX_LINK_DEPTH => {
+                        pending.push_back((target.trim().to_string(), depth + 1));
+                    }
+                    Some(_) => {}
+                    None => entries.push((self.keys[index].text.clone(), text)),
+                }
+            }
+        }
+        return Ok(entries);
+    }
+
+    fn decode(&self, record: &[u8]) -> String {
+        let (text, _) = self.header.encoding.decode_without_bom_handling(record);
+        return text.trim_end_matches('\0').to_string();
+    }
+
+    /// 读取第 `index` 个词头的内容
+    fn record(&self, index: usize) -> Result<Vec<u8>> {
+        let start = self.keys[index].offset;
+        // 内容的结束位置为下一个词头的起始位置
+        let end = self
+            .keys
+            .get(index + 1)
+            .map_or(self.records_size, |key| key.offset);
+        if start > end || end > self.records_size {
+            return Err(Error::invalid_dictionary(format!(
+                "record of {} is out of range in {}",
+                self.keys[index].text,
+                self.path.display()
+            )));
+        }
+        let mut record = vec![];
+        let mut position = start;
+        // 内容可能跨越多个块
+        while position < end {
+            let block_index = self
+                .blocks
+                .partition_point(|block| block.offset + block.decompressed_size <= position);
+            let block = self.blocks.get(block_index).ok_or_else(|| {
+                Error::invalid_dictionary(format!(
+                    "record of {} is out of range in {}",
+                    self.keys[index].text,
+                    self.path.display()
+                ))
+            })?;
+            let block_end = block.offset + block.decompressed_size;
+            let slice_end = end.min(block_end);
+            self.with_block(block_index, |data| {
+                let from = (position - block.offset) as usize;
+                let to = (slice_end - block.offset) as usize;
+                let slice = data
+                    .get(from..to)
+                    .ok_or_else(|| Error::invalid_dictionary("record block is too short"))?;
+                // 预留剩余内容的空间，但不超过已解压的块的大小，以免文件中的错误数据导致过量分配
+                if record.is_empty() {
+                    record.reserve(to_usize(end - position)?.min(data.len()));
+                }
+                record.extend_from_slice(slice);
+                return Ok(());
+            })?;
+            position = slice_end;
+        }
+        return Ok(record);
+    }
+
+    fn with_block(&self, block_index: usize, f: impl FnOnce(&[u8]) -> Result<()>) -> Result<()> {
+        let mut last_block = self
+            .last_block
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some((cached_index, data)) = last_block.as_ref() {
+            if *cached_index == block_index {
+                return f(data);
+            }
+        }
+        let block = &self.blocks[block_index];
+        let compressed = {
+            let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+            file.seek(SeekFrom::Start(block.file_offset))
+                .map_err(|e| Error::io("failed to seek in MDict file", e).with_path(&self.path))?;
+            read_vec(&mut *file, to_usize(block.compressed_size)?)
+                .map_err(|e| e.with_path(&self.path))?
+        };
+        let data = decompress_block(&compressed, to_usize(block.decompressed_size)?)
+            .map_err(|e| e.with_path(&self.path))?;
+        let result = f(&data);
+        *last_block = Some((block_index, data));
+        return result;
+    }
+}
+
+/// .mdx 词典及其附带的 .mdd 资源文件
+pub struct MdxDictionary {
+    info: DictionaryInfo,
+    mdx: MDict,
+    mdds: Vec<MDict>,
+}
+
+impl MdxDictionary {
+    /// 打开 .mdx 文件，并加载同目录下同名的 .mdd 文件（`name.mdd`、`name.1.mdd`、`name.2.mdd`……）
+    pub fn open(id: impl Into<String>, mdx_path: impl AsRef<Path>) -> Result<Self> {
+        fn inner(id: String, mdx_path: &Path) -> Result<MdxDictionary> {
+            let mdx = MDict::open(mdx_path)?;
+            let mut mdds = vec![];
+            let mut mdd_path = mdx_path.with_extension("mdd");
+            let mut number = 1;
+            while mdd_path.is_file() {
+                mdds.push(MDict::open(&mdd_path)?);
+                mdd_path = mdx_path.with_extension(format!("{number}.mdd"));
+                number += 1;
+            }
+            let name = mdx
+                .header()
+                .title
+                .clone()
+                .filter(|title| !title.is_empty() && !title.starts_with("Title (No HTML"))
+                .unwrap_or_else(|| id.clone());
+            return Ok(MdxDictionary {
+                info: DictionaryInfo {
+                    id,
+                    name,
+                    source_language: None,
+                    target_language: None,
+                    builtin: false,
+                },
+                mdx,
+                mdds,
+            });
+        }
+        return inner(id.into(), mdx_path.as_ref());
+    }
+}
+
+impl Dictionary for MdxDictionary {
+    fn info(&self) -> &DictionaryInfo {
+        return &self.info;
+    }
+
+    fn search(&self, word: &str) -> Result<Vec<DictionaryEntry>> {
+        let entries = self
+            .mdx
+            .lookup_text(word)?
+            .into_iter()
+            .map(|(word, html)| DictionaryEntry {
+                word,
+                html: Some(html),
+                ..Default::default()
+            })
+            .collect();
+        return Ok(entries);
+    }
+
+    fn resource(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        for mdd in &self.mdds {
+            if let Some(data) = mdd.lookup(path)?.into_iter().next() {
+                return Ok(Some(data));
+            }
+        }
+        return Ok(None);
+    }
+}
+
+fn build_index(
+    keys: &[MDictKey],
+    kind: MDictKind,
+    case_sensitive: bool,
+) -> HashMap<String, Vec<usize>> {
+    let mut index: HashMap<String, Vec<usize>> = HashMap::with_capacity(keys.len());
+    for (i, key) in keys.iter().enumerate() {
+        index
+            .entry(normalize_key(&key.text, kind, case_sensitive))
+            .or_default()
+            .push(i);
+    }
+    return index;
+}
+
+/// 规范化词头：词条文件忽略首尾空白（及大小写），资源文件统一为 `\dir\file` 形式的路径
+fn normalize_key(key: &str, kind: MDictKind, case_sensitive: bool) -> String {
+    let key = match kind {
+        MDictKind::Mdx => key.trim().to_string(),
+        MDictKind::Mdd => {
+            let path = key.trim().replace('/', "\\");
+            if path.starts_with('\\') {
+                path
+            } else {
+                format!("\\{path}")
+            }
+        }
+    };
+    if case_sensitive && kind == MDictKind::Mdx {
+        return key;
+    }
+    return key.to_lowercase();
+}
+
+fn parse_header(bytes: &[u8], kind: MDictKind) -> Result<MDictHeader> {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    let text = String::from_utf16_lossy(&units);
+    let attributes = parse_attributes(text.trim_end_matches('\0'));
+    let version = attributes
+        .get("GeneratedByEngineVersion")
+        .and_then(|version| version.trim().parse::<f32>().ok())
+        .ok_or_else(|| {
+            Error::invalid_dictionary("header does not contain a valid GeneratedByEngineVersion")
+        })?;
+    let encrypted = match attributes.get("Encrypted").map(|s| s.trim()) {
+        None | Some("") | Some("No") => 0,
+        Some("Yes") => 1,
+        Some(value) => value
+            .parse::<u32>()
+            .map_err(|_| Error::invalid_dictionary(format!("unknown encryption flag {value}")))?,
+    };
+    let encoding = match kind {
+        // 资源文件的词头总是 UTF-16LE
+        MDictKind::Mdd => encoding_rs::UTF_16LE,
+        MDictKind::Mdx => match attributes.get("Encoding").map(|s| s.trim()) {
+            None | Some("") => encoding_rs::UTF_8,
+            Some(label) if label.eq_ignore_ascii_case("UTF-16") => encoding_rs::UTF_16LE,
+            // GBK 和 GB2312 都按其超集 GB18030 解码
+            Some(label)
+                if label.eq_ignore_ascii_case("GBK") || label.eq_ignore_ascii_case("GB2312") =>
+            {
+                encoding_rs::GB18030
+            }
+            Some(label) => Encoding::for_label(label.as_bytes()).ok_or_else(|| {
+                Error::invalid_dictionary(format!("unsupported encoding {label}"))
+            })?,
+        },
+    };
+    return Ok(MDictHeader {
+        version,
+        title: attributes.get("Title").cloned(),
+        encoding,
+        key_case_sensitive: attributes
+            .get("KeyCaseSensitive")
+            .is_some_and(|value| value.trim() == "Yes"),
+        encrypted,
+    });
+}
+
+/// 解析 `<Dictionary Name="value" ... />` 中的属性
+fn parse_attributes(text: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = text;
+    while let Some(eq) = rest.find("=\"") {
+        let name = rest[..eq]
+            .rsplit(|c: char| c.is_whitespace() || c == '<')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let value_start = eq + 2;
+        let Some(value_len) = rest[value_start..].find('"') else {
+            break;
+        };
+        let value = &rest[value_start..value_start + value_len];
+        let value = value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&");
+        attributes.insert(name, value);
+        rest = &rest[value_start + value_len + 1..];
+    }
+    return attributes;
+}
+
+/// 解析索引信息，返回每个词头块的压缩后大小和解压后大小
+fn parse_key_block_info(
+    info: &[u8],
+    header: &MDictHeader,
+    num_blocks: u64,
+) -> Result<Vec<(u64, u64)>> {
+    let v2 = header.version >= 2.0;
+    let utf16 = header.encoding == encoding_rs::UTF_16LE;
+    // 版本 2 中首尾词头带有结束符
+    let terminator = if v2 { 1 } else { 0 };
+    let unit = if utf16 { 2 } else { 1 };
+    let mut reader = ByteReader::new(info, v2);
+    let mut sizes = vec![];
+    for _ in 0..num_blocks {
+        reader.number()?;
+        for _ in 0..2 {
+            // 块中第一个和最后一个词头
+            let text_size = if v2 {
+                reader.u16()? as usize
+            } else {
+                reader.u8()? as usize
+            };
+            reader.bytes((text_size + terminator) * unit)?;
+        }
+        let compressed_size = reader.number()?;
+        let decompressed_size = reader.number()?;
+        sizes.push((compressed_size, decompressed_size));
+    }
+    return Ok(sizes);
+}
+
+/// 解析词头块：每个词头为（内容起始位置，以 0 结尾的词头文本）
+fn parse_key_block(
+    block: &[u8],
+    header: &MDictHeader,
+    v2: bool,
+    keys: &mut Vec<MDictKey>,
+) -> Result<()> {
+    let utf16 = header.encoding == encoding_rs::UTF_16LE;
+    let mut reader = ByteReader::new(block, v2);
+    while !reader.is_empty() {
+        let offset = reader.number()?;
+        let rest = reader.rest();
+        let text_len = if utf16 {
+            rest.chunks_exact(2)
+                .position(|pair| pair == [0, 0])
+                .map(|units| units * 2)
+        } else {
+            rest.iter().position(|&byte| byte == 0)
+        }
+        .ok_or_else(|| Error::invalid_dictionary("key text is not terminated"))?;
+        let (text, _) = header
+            .encoding
+            .decode_without_bom_handling(&rest[..text_len]);
+        keys.push(MDictKey {
+            text: text.into_owned(),
+            offset,
+        });
+        reader.bytes(text_len + if utf16 { 2 } else { 1 })?;
+    }
+    return Ok(());
+}
+
+/// 解压一个块：4 字节压缩类型（小端序），4 字节解压后数据的 Adler-32 校验和（大端序），之后为数据
+///
+/// `decompressed_size` 来自文件中的索引，不可信：解压结果超过该大小时立即返回错误，预分配的内存也不超过
+/// 按压缩数据大小估计的上限。
+fn decompress_block(block: &[u8], decompressed_size: usize) -> Result<Vec<u8>> {
+    if block.len() < 8 {
+        return Err(Error::invalid_dictionary("compressed block is too short"));
+    }
+    let kind = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+    let checksum = u32::from_be_bytes([block[4], block[5], block[6], block[7]]);
+    let data = &block[8..];
+    let decompressed = match kind {
+        0 => data.to_vec(),
+        1 => lzo::decompress(
+            data,
+            decompressed_size,
+            preallocation_size(decompressed_size, data),
+        )?,
+        2 => {
+            let mut decompressed = Vec::with_capacity(preallocation_size(decompressed_size, data));
+            // 多读 1 字节，以便发现超出声明大小的数据
+            flate2::read::ZlibDecoder::new(data)
+                .take(decompressed_size as u64 + 1)
+                .read_to_end(&mut decompressed)
+                .map_err(|e| Error::io("failed to decompress zlib block", e))?;
+            decompressed
+        }
+        _ => {
+            return Err(Error::invalid_dictionary(format!(
+                "unknown compression type {kind}"
+            )))
+        }
+    };
+    if decompressed.len() != decompressed_size {
+        return Err(Error::invalid_dictionary(
+            "decompressed block has an unexpected size",
+        ));
+    }
+    if adler32(&decompressed) != checksum {
+        return Err(Error::invalid_dictionary("block checksum mismatch"));
+    }
+    return Ok(decompressed);
+}
+
+/// 按解压后的大小预分配内存时的上限，声明的大小超过按压缩数据估计的大小时只按估计值预分配
+fn preallocation_size(decompressed_size: usize, compressed: &[u8]) -> usize {
+    return decompressed_size
+        .min(compressed.len().saturating_mul(16))
+        .min(1 << 24);
+}
+
+/// 解密索引信息（Encrypted 的第 1 位），密钥由块中的校验和派生，无需注册码
+fn decrypt_key_block_info(info: &mut [u8]) -> Result<()> {
+    use ripemd::{Digest, Ripemd128};
+    if info.len() < 8 {
+        return Err(Error::invalid_dictionary("key block info is too short"));
+    }
+    let mut hasher = Ripemd128::new();
+    hasher.update(&info[4..8]);
+    hasher.update(0x3695u32.to_le_bytes());
+    let key = hasher.finalize();
+    let mut previous = 0x36u8;
+    for (i, byte) in info[8..].iter_mut().enumerate() {
+        let encrypted = *byte;
+        *byte = encrypted.rotate_left(4) ^ previous ^ (i as u8) ^ key[i % key.len()];
+        previous = encrypted;
+    }
+    return Ok(());
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 每 5552 字节取一次模，保证不会溢出
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    return (b << 16) | a;
+}
+
+fn to_usize(n: u64) -> Result<usize> {
+    return usize::try_from(n)
+        .map_err(|_| Error::invalid_dictionary(format!("size {n} is too large")));
+}
+
+fn read_array<const N: usize>(file: &mut impl Read) -> Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    file.read_exact(&mut buf)
+        .map_err(|e| Error::io("unexpected end of file", e))?;
+    return Ok(buf);
+}
+
+fn read_vec(file: &mut impl Read, size: usize) -> Result<Vec<u8>> {
+    let mut buf = vec![];
+    file.take(size as u64)
+        .read_to_end(&mut buf)
+        .map_err(|e| Error::io("failed to read file", e))?;
+    if buf.len() != size {
+        return Err(Error::invalid_dictionary("unexpected end of file"));
+    }
+    return Ok(buf);
+}
+
+/// 按大端序读取数字，版本 2 中的数字为 8 字节，版本 1 中为 4 字节
+struct ByteReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    wide: bool,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8], wide: bool) -> Self {
+        return ByteReader {
+            data,
+            position: 0,
+            wide,
+        };
+    }
+
+    fn is_empty(&self) -> bool {
+        return self.position >= self.data.len();
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        return &self.data[self.position.min(self.data.len())..];
+    }
+
+    fn bytes(&mut self, size: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position.saturating_add(size))
+            .ok_or_else(|| Error::invalid_dictionary("unexpected end of data"))?;
+        self.position += size;
+        return Ok(bytes);
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        return Ok(self.bytes(1)?[0]);
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.bytes(2)?;
+        return Ok(u16::from_be_bytes([bytes[0], bytes[1]]));
+    }
+
+    fn number(&mut self) -> Result<u64> {
+        if self.wide {
+            let bytes = self.bytes(8)?;
+            return Ok(u64::from_be_bytes(
+                bytes.try_into().expect("unexpected size"),
+            ));
+        }
+        let bytes = self.bytes(4)?;
+        return Ok(u32::from_be_bytes(bytes.try_into().expect("unexpected size")) as u64);
+    }
+}
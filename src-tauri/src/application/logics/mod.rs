@@ -1,3 +1,21 @@
+pub mod cache;
 pub mod config;
+pub mod config_migration;
 pub mod dict;
+pub mod error;
+pub mod frequency;
+pub mod fts;
+pub mod grammar;
+pub mod history;
+pub mod lzo;
+pub mod mdict;
+pub mod morphology;
+pub mod overlay;
+pub mod phonetics;
+pub mod pool;
+pub mod registry;
+pub mod stardict;
+pub mod userdb;
 pub mod utils;
+pub mod vocabulary;
+pub mod word_index;
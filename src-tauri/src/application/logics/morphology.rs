@@ -0,0 +1,399 @@
+//! 基于规则的英语词形还原，仅生成候选原型，是否为真实单词需由调用方查词典确认
+
+/// 由词形推导原型时使用的规则
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LemmaRule {
+    /// -ies → -y（studies → study）
+    Ies,
+    /// -ied → -y（studied → study）
+    Ied,
+    /// -es（boxes → box）
+    Es,
+    /// -s（cats → cat）
+    S,
+    /// -ed（walked → walk）
+    Ed,
+    /// -ed → -e（hoped → hope）
+    EdWithE,
+    /// 双写辅音 + -ed（stopped → stop）
+    EdDoubled,
+    /// -ing（walking → walk）
+    Ing,
+    /// -ing → -e（making → make）
+    IngWithE,
+    /// 双写辅音 + -ing（running → run）
+    IngDoubled,
+    /// -ying → -ie（lying → lie）
+    Ying,
+    /// -er（faster → fast）
+    Er,
+    /// -er → -e（later → late）
+    ErWithE,
+    /// 双写辅音 + -er（bigger → big）
+    ErDoubled,
+    /// -ier → -y（happier → happy）
+    Ier,
+    /// -est（fastest → fast）
+    Est,
+    /// -est → -e（latest → late）
+    EstWithE,
+    /// 双写辅音 + -est（biggest → big）
+    EstDoubled,
+    /// -iest → -y（happiest → happy）
+    Iest,
+    /// 所有格 -'s 或 -s'（John's → John）
+    Possessive,
+    /// 去掉连字符（e-mail → email）
+    HyphenRemoved,
+    /// 连字符改为空格（ice-cream → ice cream）
+    HyphenToSpace,
+    /// 英式拼写改为美式拼写（colour → color）
+    AmericanSpelling,
+    /// 美式拼写改为英式拼写（color → colour）
+    BritishSpelling,
+}
+
+/// 屈折变化的类型
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FormKind {
+    /// 名词复数，或动词第三人称单数（二者形式相同，无法区分）
+    Plural,
+    Past,
+    PastParticiple,
+    /// -ing 形式
+    PresentParticiple,
+    Comparative,
+    Superlative,
+}
+
+/// 一个候选原型及推导它所用的规则（按应用顺序排列）
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BaseCandidate {
+    pub base: String,
+    pub rules: Vec<LemmaRule>,
+}
+
+/// 生成单词所有可能的原型（未经词典验证），不包括单词本身
+pub fn candidate_bases(word: &str) -> Vec<BaseCandidate> {
+    let word = word.trim();
+    let mut candidates: Vec<BaseCandidate> = vec![];
+    let mut push = |base: String, rules: Vec<LemmaRule>| {
+        let duplicated = base.eq_ignore_ascii_case(word)
+            || candidates
+                .iter()
+                .any(|c| c.base.eq_ignore_ascii_case(&base));
+        if !base.is_empty() && !duplicated {
+            candidates.push(BaseCandidate { base, rules });
+        }
+    };
+    // 先去掉所有格，再对结果应用其余规则
+    let mut stems = vec![(word.to_string(), vec![])];
+    if let Some(stem) = strip_possessive(word) {
+        push(stem.clone(), vec![LemmaRule::Possessive]);
+        stems.push((stem, vec![LemmaRule::Possessive]));
+    }
+    for (stem, rules) in stems {
+        let mut inflections = vec![(stem.clone(), rules.clone())];
+        for (base, rule) in strip_suffix(&stem) {
+            let mut rules = rules.clone();
+            rules.push(rule);
+            push(base.clone(), rules.clone());
+            inflections.push((base, rules));
+        }
+        for (base, rules) in inflections {
+            for (variant, rule) in spelling_variants(&base) {
+                let mut rules = rules.clone();
+                rules.push(rule);
+                push(variant, rules);
+            }
+            if base.contains('-') {
+                let mut joined_rules = rules.clone();
+                joined_rules.push(LemmaRule::HyphenRemoved);
+                push(base.replace('-', ""), joined_rules);
+                let mut spaced_rules = rules.clone();
+                spaced_rules.push(LemmaRule::HyphenToSpace);
+                push(base.replace('-', " "), spaced_rules);
+            }
+        }
+    }
+    return candidates;
+}
+
+fn strip_possessive(word: &str) -> Option<String> {
+    for suffix in ["'s", "’s"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            return Some(stem.to_string());
+        }
+    }
+    for suffix in ["s'", "s’"] {
+        if word.ends_with(suffix) {
+            return Some(word[..word.len() - suffix.len() + 1].to_string());
+        }
+    }
+    return None;
+}
+
+/// 去掉屈折变化的后缀，返回候选原型及对应规则
+fn strip_suffix(word: &str) -> Vec<(String, LemmaRule)> {
+    // 后缀均为 ASCII，按 ASCII 小写形式匹配后，在原词上截取即可保留大小写
+    let lower = word.to_ascii_lowercase();
+    let stem = |suffix_len: usize| word[..word.len() - suffix_len].to_string();
+    let mut bases = vec![];
+    if lower.len() > 4 && lower.ends_with("ies") {
+        bases.push((stem(3) + "y", LemmaRule::Ies));
+    }
+    if lower.len() > 4 && lower.ends_with("ied") {
+        bases.push((stem(3) + "y", LemmaRule::Ied));
+    }
+    let es = (lower.len() > 3 && lower.ends_with("es")).then(|| (stem(2), LemmaRule::Es));
+    let s = (lower.len() > 2 && lower.ends_with('s') && !lower.ends_with("ss"))
+        .then(|| (stem(1), LemmaRule::S));
+    // 调用方取第一个存在的候选，因此只有词干以 ss、x、z、ch、sh 结尾时 -es 才排在 -s 之前
+    // （classes → class），其他情况 -s 优先（uses → use 而不是 us）
+    let es_first = lower.strip_suffix("es").is_some_and(|stem| {
+        ["ss", "x", "z", "ch", "sh"]
+            .iter()
+            .any(|end| stem.ends_with(end))
+    });
+    if es_first {
+        bases.extend(es);
+        bases.extend(s);
+    } else {
+        bases.extend(s);
+        bases.extend(es);
+    }
+    push_stripped(
+        &mut bases,
+        word,
+        &lower,
+        "ed",
+        [LemmaRule::Ed, LemmaRule::EdWithE, LemmaRule::EdDoubled],
+    );
+    if lower.len() > 4 && lower.ends_with("ying") {
+        bases.push((stem(4) + "ie", LemmaRule::Ying));
+    }
+    push_stripped(
+        &mut bases,
+        word,
+        &lower,
+        "ing",
+        [LemmaRule::Ing, LemmaRule::IngWithE, LemmaRule::IngDoubled],
+    );
+    if lower.len() > 4 && lower.ends_with("ier") {
+        bases.push((stem(3) + "y", LemmaRule::Ier));
+    }
+    push_stripped(
+        &mut bases,
+        word,
+        &lower,
+        "er",
+        [LemmaRule::Er, LemmaRule::ErWithE, LemmaRule::ErDoubled],
+    );
+    if lower.len() > 5 && lower.ends_with("iest") {
+        bases.push((stem(4) + "y", LemmaRule::Iest));
+    }
+    push_stripped(
+        &mut bases,
+        word,
+        &lower,
+        "est",
+        [LemmaRule::Est, LemmaRule::EstWithE, LemmaRule::EstDoubled],
+    );
+    return bases;
+}
+
+/// 处理 -ed、-ing、-er、-est 这类后缀：直接去掉、去掉后补 -e、去掉双写的辅音，
+/// 最后一个参数依次为这三种情况对应的规则
+fn push_stripped(
+    bases: &mut Vec<(String, LemmaRule)>,
+    word: &str,
+    lower: &str,
+    suffix: &str,
+    [plain, with_e, doubled]: [LemmaRule; 3],
+) {
+    if lower.len() < suffix.len() + 2 || !lower.ends_with(suffix) {
+        return;
+    }
+    let stem = &word[..word.len() - suffix.len()];
+    let lower_stem = &lower[..lower.len() - suffix.len()];
+    let with_e_base = (format!("{stem}e"), with_e);
+    let plain_base = (stem.to_string(), plain);
+    // 以“辅音-元音-辅音”结尾的短词加后缀时通常会双写辅音（hop → hopped），
+    // 因此 "hoped" 更可能来自 "hope" 而非 "hop"
+    if ends_with_cvc(lower_stem) {
+        bases.push(with_e_base);
+        bases.push(plain_base);
+    } else {
+        bases.push(plain_base);
+        bases.push(with_e_base);
+    }
+    // 双写的辅音（stopped → stop，英式拼写的 travelled → travel）
+    if let [.., a, b] = lower_stem.as_bytes() {
+        if a == b && is_consonant(*a as char) {
+            bases.push((stem[..stem.len() - 1].to_string(), doubled));
+        }
+    }
+}
+
+fn is_consonant(c: char) -> bool {
+    return c.is_ascii_alphabetic() && !matches!(c, 'a' | 'e' | 'i' | 'o' | 'u');
+}
+
+fn ends_with_cvc(word: &str) -> bool {
+    return match word.as_bytes() {
+        [.., a, b, c] => {
+            let (a, b, c) = (*a as char, *b as char, *c as char);
+            is_consonant(a)
+                && b.is_ascii_alphabetic()
+                && !is_consonant(b)
+                && is_consonant(c)
+                && !matches!(c, 'w' | 'x' | 'y')
+        }
+        _ => false,
+    };
+}
+
+/// 英式与美式拼写之间的转换，返回另一种拼写及对应规则
+fn spelling_variants(word: &str) -> Vec<(String, LemmaRule)> {
+    const BRITISH_TO_AMERICAN: &[(&str, &str)] = &[
+        ("our", "or"),
+        ("isation", "ization"),
+        ("ise", "ize"),
+        ("yse", "yze"),
+        ("tre", "ter"),
+        ("ogue", "og"),
+        ("ence", "ense"),
+    ];
+    let lower = word.to_ascii_lowercase();
+    let mut variants = vec![];
+    for (british, american) in BRITISH_TO_AMERICAN {
+        if lower.len() > british.len() + 1 && lower.ends_with(british) {
+            let stem = &word[..word.len() - british.len()];
+            variants.push((format!("{stem}{american}"), LemmaRule::AmericanSpelling));
+        }
+        if lower.len() > american.len() + 1 && lower.ends_with(american) {
+            let stem = &word[..word.len() - american.len()];
+            variants.push((format!("{stem}{british}"), LemmaRule::BritishSpelling));
+        }
+    }
+    return variants;
+}
+
+/// 按规则变化生成原型的各种词形
+///
+/// 比较级和最高级只对单音节词和以 -y 结尾的双音节词生成。
+pub fn regular_forms(base: &str) -> Vec<(String, Vec<FormKind>)> {
+    let base = base.trim();
+    let lower = base.to_ascii_lowercase();
+    if lower.len() < 2 || !lower.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return vec![];
+    }
+    let bytes = lower.as_bytes();
+    let last = bytes[bytes.len() - 1] as char;
+    let before_last = bytes[bytes.len() - 2] as char;
+    let without_last = &base[..base.len() - 1];
+    let consonant_y = last == 'y' && is_consonant(before_last);
+    let syllables = count_syllables(&lower);
+    // 单音节且以“辅音-元音-辅音”结尾的词需要双写末尾辅音（stop → stopped）
+    let doubled = if syllables == 1 && ends_with_cvc(&lower) {
+        format!("{base}{last}")
+    } else {
+        base.to_string()
+    };
+    let mut forms = vec![];
+    let plural = if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|suffix| lower.ends_with(suffix))
+    {
+        format!("{base}es")
+    } else if consonant_y {
+        format!("{without_last}ies")
+    } else {
+        format!("{base}s")
+    };
+    forms.push((plural, vec![FormKind::Plural]));
+    let past = if last == 'e' {
+        format!("{base}d")
+    } else if consonant_y {
+        format!("{without_last}ied")
+    } else {
+        format!("{doubled}ed")
+    };
+    forms.push((past, vec![FormKind::Past, FormKind::PastParticiple]));
+    let present_participle = if lower.ends_with("ie") {
+        format!("{}ying", &base[..base.len() - 2])
+    } else if last == 'e' && !["ee", "ye", "oe"].iter().any(|s| lower.ends_with(s)) {
+        format!("{without_last}ing")
+    } else {
+        format!("{doubled}ing")
+    };
+    forms.push((present_participle, vec![FormKind::PresentParticiple]));
+    if syllables == 1 || (syllables == 2 && consonant_y) {
+        let (comparative, superlative) = if last == 'e' {
+            (format!("{base}r"), format!("{base}st"))
+        } else if consonant_y {
+            (format!("{without_last}ier"), format!("{without_last}iest"))
+        } else {
+            (format!("{doubled}er"), format!("{doubled}est"))
+        };
+        forms.push((comparative, vec![FormKind::Comparative]));
+        forms.push((superlative, vec![FormKind::Superlative]));
+    }
+    return forms;
+}
+
+/// 推断 `form` 是 `base` 的哪种屈折变化，无法推断时（如不规则变化）返回空数组
+pub fn infer_form_kinds(base: &str, form: &str) -> Vec<FormKind> {
+    for candidate in candidate_bases(form) {
+        if !candidate.base.eq_ignore_ascii_case(base) {
+            continue;
+        }
+        let kinds = match candidate.rules.last() {
+            Some(LemmaRule::Ies | LemmaRule::Es | LemmaRule::S) => {
+                vec![FormKind::Plural]
+            }
+            Some(LemmaRule::Ied | LemmaRule::Ed | LemmaRule::EdWithE | LemmaRule::EdDoubled) => {
+                vec![FormKind::Past, FormKind::PastParticiple]
+            }
+            Some(
+                LemmaRule::Ing | LemmaRule::IngWithE | LemmaRule::IngDoubled | LemmaRule::Ying,
+            ) => vec![FormKind::PresentParticiple],
+            Some(LemmaRule::Er | LemmaRule::ErWithE | LemmaRule::ErDoubled | LemmaRule::Ier) => {
+                vec![FormKind::Comparative]
+            }
+            Some(
+                LemmaRule::Est | LemmaRule::EstWithE | LemmaRule::EstDoubled | LemmaRule::Iest,
+            ) => vec![FormKind::Superlative],
+            _ => continue,
+        };
+        return kinds;
+    }
+    // 不规则变化中只有 -ing 形式可以可靠地识别
+    let lower_form = form.to_ascii_lowercase();
+    if lower_form.ends_with("ing") && !base.to_ascii_lowercase().ends_with("ing") {
+        return vec![FormKind::PresentParticiple];
+    }
+    return vec![];
+}
+
+/// 粗略估计音节数（连续的元音字母计为一个音节，词尾不发音的 e 不计）
+fn count_syllables(lower: &str) -> usize {
+    let bytes = lower.as_bytes();
+    let mut count = 0;
+    let mut in_vowel = false;
+    for (i, &b) in bytes.iter().enumerate() {
+        let c = b as char;
+        let vowel = matches!(c, 'a' | 'e' | 'i' | 'o' | 'u') || (c == 'y' && i > 0);
+        if vowel && !in_vowel {
+            count += 1;
+        }
+        in_vowel = vowel;
+    }
+    if count > 1 && lower.ends_with('e') && !lower.ends_with("le") && !lower.ends_with("ee") {
+        count -= 1;
+    }
+    return count.max(1);
+}
@@ -0,0 +1,283 @@
+//! 用户词典：存放在 user.db 中、由用户添加的词条，查询时与自带的 collins、oxford 词条合并
+
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use super::error::{Error, ErrorKind, Result};
+use super::grammar::{self, Grammar};
+use super::phonetics::{self, Phonetics};
+use super::userdb::now_millis;
+
+/// 用户词典中的一个词条
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserEntry {
+    pub id: i64,
+    pub word: String,
+    pub phonetic: Option<String>,
+    /// 由 phonetic 拆分出的英式、美式音标
+    pub phonetics: Option<Phonetics>,
+    pub sense: Option<String>,
+    pub en_def: Option<String>,
+    pub cn_def: Option<String>,
+    pub notes: Option<String>,
+    /// 是否代替自带的词条，为 `true` 时查询此单词不再返回 collins、oxford 中的词条
+    pub replace_builtin: bool,
+    /// 添加或最后修改的时间，Unix 时间戳（毫秒）
+    pub time: i64,
+    /// 由 sense 解析出的词性和语法标注
+    pub grammar: Option<Grammar>,
+}
+
+/// 添加、修改词条以及导入导出时使用的词条内容，导出的 JSON 文件即为此格式的数组
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserEntryContent {
+    pub word: String,
+    #[serde(default)]
+    pub phonetic: Option<String>,
+    #[serde(default)]
+    pub sense: Option<String>,
+    #[serde(default)]
+    pub en_def: Option<String>,
+    #[serde(default)]
+    pub cn_def: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub replace_builtin: bool,
+}
+
+impl UserEntryContent {
+    /// 去掉首尾空白，空字符串视为未填写，单词为空时返回错误
+    fn normalized(self) -> Result<Self> {
+        fn field(value: Option<String>) -> Option<String> {
+            return value
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty());
+        }
+        let word = self.word.trim().to_string();
+        if word.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidValue,
+                "word of user dictionary entry is empty",
+            ));
+        }
+        return Ok(UserEntryContent {
+            word,
+            phonetic: field(self.phonetic),
+            sense: field(self.sense),
+            en_def: field(self.en_def),
+            cn_def: field(self.cn_def),
+            notes: field(self.notes),
+            replace_builtin: self.replace_builtin,
+        });
+    }
+}
+
+const SELECT_ENTRY: &str =
+    "select id, word, phonetic, sense, en_def, cn_def, notes, replace_builtin, time from user_entries";
+
+fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<UserEntry> {
+    let phonetic: Option<String> = row.get("phonetic")?;
+    let sense: Option<String> = row.get("sense")?;
+    return Ok(UserEntry {
+        id: row.get("id")?,
+        word: row.get("word")?,
+        phonetics: phonetics::parse_phonetics(phonetic.as_deref()),
+        phonetic,
+        grammar: grammar::parse_grammar(sense.as_deref(), None),
+        sense,
+        en_def: row.get("en_def")?,
+        cn_def: row.get("cn_def")?,
+        notes: row.get("notes")?,
+        replace_builtin: row.get("replace_builtin")?,
+        time: row.get("time")?,
+    });
+}
+
+fn get_user_entry(conn: &Connection, id: i64) -> Result<UserEntry> {
+    let mut stmt = conn
+        .prepare_cached(&format!("{SELECT_ENTRY} where id = ?1"))
+        .map_err(|e| Error::sqlite("failed to prepare SQL statement for user dictionary", e))?;
+    let entry = stmt.query_row([id], entry_from_row).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => Error::new(
+            ErrorKind::NotFound,
+            format!("user dictionary entry {id} does not exist"),
+        ),
+        e => Error::sqlite("failed to query user dictionary", e),
+    })?;
+    return Ok(entry);
+}
+
+/// 添加词条
+pub fn add_user_entry(conn: &Connection, content: UserEntryContent) -> Result<UserEntry> {
+    let content = content.normalized()?;
+    conn.prepare_cached(
+        "insert into user_entries (word, phonetic, sense, en_def, cn_def, notes, replace_builtin, time)
+        values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )
+    .and_then(|mut stmt| {
+        stmt.execute((
+            &content.word,
+            &content.phonetic,
+            &content.sense,
+            &content.en_def,
+            &content.cn_def,
+            &content.notes,
+            content.replace_builtin,
+            now_millis(),
+        ))
+    })
+    .map_err(|e| Error::sqlite("failed to add user dictionary entry", e))?;
+    return get_user_entry(conn, conn.last_insert_rowid());
+}
+
+/// 修改词条
+pub fn update_user_entry(
+    conn: &Connection,
+    id: i64,
+    content: UserEntryContent,
+) -> Result<UserEntry> {
+    let content = content.normalized()?;
+    let updated = conn
+        .prepare_cached(
+            "update user_entries set word = ?2, phonetic = ?3, sense = ?4, en_def = ?5, cn_def = ?6,
+            notes = ?7, replace_builtin = ?8, time = ?9 where id = ?1",
+        )
+        .and_then(|mut stmt| {
+            stmt.execute((
+                id,
+                &content.word,
+                &content.phonetic,
+                &content.sense,
+                &content.en_def,
+                &content.cn_def,
+                &content.notes,
+                content.replace_builtin,
+                now_millis(),
+            ))
+        })
+        .map_err(|e| Error::sqlite("failed to update user dictionary entry", e))?;
+    if updated == 0 {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("user dictionary entry {id} does not exist"),
+        ));
+    }
+    return get_user_entry(conn, id);
+}
+
+/// 删除词条，返回词条是否存在
+pub fn delete_user_entry(conn: &Connection, id: i64) -> Result<bool> {
+    let deleted = conn
+        .execute("delete from user_entries where id = ?1", [id])
+        .map_err(|e| Error::sqlite("failed to delete user dictionary entry", e))?;
+    return Ok(deleted > 0);
+}
+
+/// 用户词典中的所有词条，按单词排列
+pub fn list_user_entries(conn: &Connection) -> Result<Vec<UserEntry>> {
+    let mut stmt = conn
+        .prepare_cached(&format!("{SELECT_ENTRY} order by word collate nocase, id"))
+        .map_err(|e| Error::sqlite("failed to prepare SQL statement for user dictionary", e))?;
+    let entries = stmt
+        .query_map([], entry_from_row)
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| Error::sqlite("failed to query user dictionary", e))?;
+    return Ok(entries);
+}
+
+/// 查询单词在用户词典中的词条（不区分大小写），按添加顺序排列
+pub fn search_user_entries(conn: &Connection, word: impl AsRef<str>) -> Result<Vec<UserEntry>> {
+    fn inner(conn: &Connection, word: &str) -> Result<Vec<UserEntry>> {
+        let mut stmt = conn
+            .prepare_cached(&format!(
+                "{SELECT_ENTRY} where word = ?1 collate nocase order by id"
+            ))
+            .map_err(|e| Error::sqlite("failed to prepare SQL statement for user dictionary", e))?;
+        let entries = stmt
+            .query_map([word.trim()], entry_from_row)
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| Error::sqlite("failed to query user dictionary", e))?;
+        return Ok(entries);
+    }
+    return inner(conn, word.as_ref());
+}
+
+/// 从 JSON 文件导入词条，文件内容为词条的数组（格式与导出的文件相同）
+///
+/// 与已有词条完全相同的词条不会重复导入，返回实际导入的词条数。
+pub fn import_user_entries(conn: &Connection, path: impl AsRef<Path>) -> Result<usize> {
+    fn inner(conn: &Connection, path: &Path) -> Result<usize> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::io("failed to read user dictionary file", e).with_path(path))?;
+        let contents: Vec<UserEntryContent> =
+            serde_json::from_str(text.trim_start_matches('\u{feff}')).map_err(|e| {
+                Error::new(ErrorKind::InvalidValue, "invalid user dictionary file")
+                    .with_path(path)
+                    .with_detail(e)
+            })?;
+        let tx = conn
+            .unchecked_transaction()
+            .map_err(|e| Error::sqlite("failed to begin transaction for user dictionary", e))?;
+        let mut imported = 0;
+        for content in contents {
+            let content = content.normalized().map_err(|e| e.with_path(path))?;
+            let exists: bool = tx
+                .prepare_cached(
+                    "select exists(select 1 from user_entries where word = ?1 collate nocase
+                    and phonetic is ?2 and sense is ?3 and en_def is ?4 and cn_def is ?5 and notes is ?6
+                    and replace_builtin = ?7)",
+                )
+                .and_then(|mut stmt| {
+                    stmt.query_row(
+                        (
+                            &content.word,
+                            &content.phonetic,
+                            &content.sense,
+                            &content.en_def,
+                            &content.cn_def,
+                            &content.notes,
+                            content.replace_builtin,
+                        ),
+                        |row| row.get(0),
+                    )
+                })
+                .map_err(|e| Error::sqlite("failed to query user dictionary", e))?;
+            if !exists {
+                add_user_entry(&tx, content)?;
+                imported += 1;
+            }
+        }
+        tx.commit()
+            .map_err(|e| Error::sqlite("failed to commit user dictionary import", e))?;
+        return Ok(imported);
+    }
+    return inner(conn, path.as_ref());
+}
+
+/// 将用户词典导出为 JSON 文件，返回导出的词条数
+pub fn export_user_entries(conn: &Connection, path: impl AsRef<Path>) -> Result<usize> {
+    fn inner(conn: &Connection, path: &Path) -> Result<usize> {
+        let contents: Vec<UserEntryContent> = list_user_entries(conn)?
+            .into_iter()
+            .map(|entry| UserEntryContent {
+                word: entry.word,
+                phonetic: entry.phonetic,
+                sense: entry.sense,
+                en_def: entry.en_def,
+                cn_def: entry.cn_def,
+                notes: entry.notes,
+                replace_builtin: entry.replace_builtin,
+            })
+            .collect();
+        let text = serde_json::to_string_pretty(&contents)
+            .map_err(|e| Error::internal("failed to serialize user dictionary").with_detail(e))?;
+        std::fs::write(path, text + "\n")
+            .map_err(|e| Error::io("failed to write user dictionary file", e).with_path(path))?;
+        return Ok(contents.len());
+    }
+    return inner(conn, path.as_ref());
+}
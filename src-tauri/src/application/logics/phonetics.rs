@@ -0,0 +1,147 @@
+//! 将词典中的音标字符串拆分为英式、美式音标
+//!
+//! 词典中的音标格式不统一，可能是 `/rʌn/`、`[rʌn]`、`BrE /ˈwɔːtə(r)/ NAmE /ˈwɔːtər/`、
+//! `英 [ˈwɔːtə] 美 [ˈwɔtɚ]`、`UK: /.../ US: /.../` 等。解析后的音标去掉了两侧的斜线或方括号，
+//! 由前端统一添加。
+
+/// 解析后的音标
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Phonetics {
+    /// 英式音标
+    pub uk: Option<String>,
+    /// 美式音标
+    pub us: Option<String>,
+    /// 未标明口音的音标，以及同一口音的其他读法
+    pub other: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Accent {
+    Uk,
+    Us,
+}
+
+/// 音标字符串中的一部分
+enum Part {
+    /// 口音标签，如 BrE、美
+    Label(Accent),
+    /// 一段音标，可能包含以分号或逗号分隔的多种读法
+    Transcription(String),
+}
+
+/// 解析音标字符串，字符串为空时返回 `None`
+pub fn parse_phonetics(phonetic: Option<&str>) -> Option<Phonetics> {
+    let phonetic = phonetic.map(str::trim).filter(|p| !p.is_empty())?;
+    let mut phonetics = Phonetics::default();
+    let mut pending: Option<Accent> = None;
+    for part in split_parts(phonetic) {
+        let transcription = match part {
+            Part::Label(accent) => {
+                pending = Some(accent);
+                continue;
+            }
+            Part::Transcription(transcription) => transcription,
+        };
+        let mut variants = transcription
+            .split([';', ','])
+            .map(normalize_ipa)
+            .filter(|variant| !variant.is_empty());
+        let Some(first) = variants.next() else {
+            continue;
+        };
+        let slot = match pending.take() {
+            Some(Accent::Uk) => &mut phonetics.uk,
+            Some(Accent::Us) => &mut phonetics.us,
+            None => {
+                phonetics.other.push(first);
+                phonetics.other.extend(variants);
+                continue;
+            }
+        };
+        if slot.is_none() {
+            *slot = Some(first);
+        } else {
+            phonetics.other.push(first);
+        }
+        phonetics.other.extend(variants);
+    }
+    if phonetics == Phonetics::default() {
+        return None;
+    }
+    return Some(phonetics);
+}
+
+/// 将音标字符串拆分为口音标签和音标，`/.../` 和 `[...]` 中的内容为音标，
+/// 其余部分按空白拆分，不是口音标签的连续部分也视为音标
+fn split_parts(phonetic: &str) -> Vec<Part> {
+    let mut parts = vec![];
+    let mut plain = String::new();
+    let mut chars = phonetic.chars();
+    while let Some(c) = chars.next() {
+        let close = match c {
+            '/' => '/',
+            '[' => ']',
+            _ => {
+                plain.push(c);
+                continue;
+            }
+        };
+        split_plain(&plain, &mut parts);
+        plain.clear();
+        let transcription: String = chars.by_ref().take_while(|&c| c != close).collect();
+        parts.push(Part::Transcription(transcription));
+    }
+    split_plain(&plain, &mut parts);
+    return parts;
+}
+
+fn split_plain(plain: &str, parts: &mut Vec<Part>) {
+    let mut words: Vec<&str> = vec![];
+    for word in plain.split_whitespace() {
+        let key = word.trim_matches([':', '：', ',', ';', '.']);
+        if key.is_empty() {
+            continue;
+        }
+        match parse_label(key) {
+            Some(accent) => {
+                if !words.is_empty() {
+                    parts.push(Part::Transcription(words.join(" ")));
+                    words.clear();
+                }
+                parts.push(Part::Label(accent));
+            }
+            None => words.push(word),
+        }
+    }
+    if !words.is_empty() {
+        parts.push(Part::Transcription(words.join(" ")));
+    }
+}
+
+fn parse_label(key: &str) -> Option<Accent> {
+    return match key.to_lowercase().as_str() {
+        "bre" | "br" | "uk" | "gb" | "brit" | "british" | "英" | "英式" => Some(Accent::Uk),
+        "name" | "ame" | "am" | "us" | "american" | "美" | "美式" => Some(Accent::Us),
+        _ => None,
+    };
+}
+
+/// 统一音标的写法：去掉残留的斜线和方括号，合并空白，
+/// 将 ASCII 的冒号和撇号替换为 IPA 的长音符号 ː 和重音符号 ˈ
+fn normalize_ipa(ipa: &str) -> String {
+    return ipa
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .filter(|c| !matches!(c, '/' | '[' | ']'))
+        .map(|c| match c {
+            ':' => 'ː',
+            '\'' | '’' => 'ˈ',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_string();
+}
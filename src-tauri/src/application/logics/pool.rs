@@ -0,0 +1,150 @@
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex, PoisonError};
+
+use rusqlite::Connection;
+
+use super::dict;
+use super::error::Result;
+
+/// dict.db 的只读连接池
+///
+/// 连接在需要时才打开，最多同时打开 `max_size` 个；连接用完后归还到池中供之后的查询复用，
+/// 连接都在使用中时，新的查询会等待其他查询归还连接。第一次打开连接前会检查 dict.db 是否可用。
+pub struct ConnectionPool {
+    dict_path: PathBuf,
+    max_size: usize,
+    state: Mutex<PoolState>,
+    /// 有连接被归还（或打开失败）时通知等待中的查询
+    available: Condvar,
+    /// dict.db 是否已通过检查，检查失败时下次打开连接会重新检查
+    validated: AtomicBool,
+}
+
+struct PoolState {
+    idle: Vec<Connection>,
+    /// 已打开的连接数（包括正在使用的）
+    opened: usize,
+    /// dict.db 被替换的次数，归还的连接若打开于替换之前，则直接关闭
+    generation: u64,
+}
+
+impl ConnectionPool {
+    pub fn new(dict_path: impl Into<PathBuf>, max_size: usize) -> Self {
+        return ConnectionPool {
+            dict_path: dict_path.into(),
+            max_size: max_size.max(1),
+            state: Mutex::new(PoolState {
+                idle: vec![],
+                opened: 0,
+                generation: 0,
+            }),
+            available: Condvar::new(),
+            validated: AtomicBool::new(false),
+        };
+    }
+
+    /// 取出一个连接，连接在 `PooledConnection` 被 drop 时自动归还
+    pub fn get(&self) -> Result<PooledConnection<'_>> {
+        // 连接池的状态只在锁内做简单的修改，其他线程 panic 后的状态仍然有效，可以直接恢复
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if let Some(conn) = state.idle.pop() {
+                return Ok(PooledConnection {
+                    pool: self,
+                    conn: Some(conn),
+                    generation: state.generation,
+                });
+            }
+            if state.opened < self.max_size {
+                state.opened += 1;
+                let generation = state.generation;
+                // 打开连接时不持有锁，使其他线程可以同时归还或取出连接
+                drop(state);
+                return match self.open() {
+                    Ok(conn) => Ok(PooledConnection {
+                        pool: self,
+                        conn: Some(conn),
+                        generation,
+                    }),
+                    Err(e) => {
+                        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+                        state.opened -= 1;
+                        self.available.notify_one();
+                        Err(e)
+                    }
+                };
+            }
+            state = self
+                .available
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// dict.db 被替换后切换到新文件
+    ///
+    /// 新文件通过检查后才会切换，检查失败时继续使用已打开的连接。切换后空闲的连接立即关闭，
+    /// 正在使用的连接在归还时关闭，之后取出的连接都是新打开的。返回切换后的 generation。
+    pub fn reload(&self) -> Result<u64> {
+        dict::validate_dict_db(&self.dict_path)?;
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.opened -= state.idle.len();
+        state.idle.clear();
+        state.generation += 1;
+        self.validated.store(true, Ordering::Release);
+        self.available.notify_all();
+        return Ok(state.generation);
+    }
+
+    fn open(&self) -> Result<Connection> {
+        if !self.validated.load(Ordering::Acquire) {
+            dict::validate_dict_db(&self.dict_path)?;
+            self.validated.store(true, Ordering::Release);
+        }
+        return dict::open_connection(&self.dict_path);
+    }
+
+    fn put_back(&self, conn: Connection, generation: u64) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if generation == state.generation {
+            state.idle.push(conn);
+        } else {
+            state.opened -= 1;
+            drop(conn);
+        }
+        self.available.notify_one();
+    }
+}
+
+/// 从连接池中取出的连接
+pub struct PooledConnection<'a> {
+    pool: &'a ConnectionPool,
+    conn: Option<Connection>,
+    /// 取出连接时连接池的 `generation`
+    generation: u64,
+}
+
+impl PooledConnection<'_> {
+    /// 取出连接时连接池的 generation，dict.db 每被替换一次加 1
+    pub fn generation(&self) -> u64 {
+        return self.generation;
+    }
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        return self.conn.as_ref().expect("connection has been returned");
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.put_back(conn, self.generation);
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use rusqlite::Connection;
+
+use super::dict::{self, Dictionary, DictionaryEntry, DictionaryInfo};
+use super::error::{Error, ErrorKind, Result};
+use super::grammar;
+use super::mdict::MdxDictionary;
+use super::phonetics;
+use super::stardict::STARDICT_ID;
+
+/// dict.db 中自带的词典，其 id 不能被 `dictionaries/` 中的词典占用
+pub const BUILTIN_DICTIONARY_IDS: [&str; 2] = ["collins", "oxford"];
+
+/// 自带词典的基本信息
+pub fn builtin_dictionaries() -> Vec<DictionaryInfo> {
+    return vec![
+        DictionaryInfo {
+            id: "collins".to_string(),
+            name: "柯林斯英汉双解词典".to_string(),
+            source_language: Some("en".to_string()),
+            target_language: Some("zh".to_string()),
+            builtin: true,
+        },
+        DictionaryInfo {
+            id: "oxford".to_string(),
+            name: "牛津高阶英汉双解词典".to_string(),
+            source_language: Some("en".to_string()),
+            target_language: Some("zh".to_string()),
+            builtin: true,
+        },
+    ];
+}
+
+/// `dictionaries/` 中的词典清单，文件名（不含扩展名）即为词典的 id
+///
+/// ```toml
+/// name = "医学词汇"
+/// source-language = "en"
+/// target-language = "zh"
+/// database = "medical.db" # 可省略，默认为与清单同名的 .db 文件
+/// table = "entries"
+///
+/// [columns]
+/// word = "term"           # 必填
+/// phonetic = "ipa"        # 以下均可省略
+/// sense = "pos"
+/// definition = "en"
+/// translation = "zh"
+/// ```
+struct Manifest {
+    name: String,
+    source_language: Option<String>,
+    target_language: Option<String>,
+    database: PathBuf,
+    table: String,
+    columns: ColumnMapping,
+}
+
+struct ColumnMapping {
+    word: String,
+    phonetic: Option<String>,
+    sense: Option<String>,
+    definition: Option<String>,
+    translation: Option<String>,
+}
+
+impl Manifest {
+    fn read(manifest_path: &Path) -> Result<Self> {
+        let toml_string = std::fs::read_to_string(manifest_path).map_err(|e| {
+            Error::io("failed to read dictionary manifest", e).with_path(manifest_path)
+        })?;
+        let doc = toml_string.parse::<toml_edit::DocumentMut>().map_err(|e| {
+            Error::new(
+                ErrorKind::TomlParse,
+                "failed to parse toml from dictionary manifest",
+            )
+            .with_path(manifest_path)
+            .with_detail(e)
+        })?;
+        let get_str = |item: Option<&toml_edit::Item>, key: &str| -> Result<Option<String>> {
+            return match item {
+                None => Ok(None),
+                Some(item) => item.as_str().map(|s| Some(s.to_string())).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidValue,
+                        format!(r#"the value of "{key}" is not a string"#),
+                    )
+                    .with_path(manifest_path)
+                }),
+            };
+        };
+        let missing_key = |key: &str| -> Error {
+            return Error::new(
+                ErrorKind::MissingKey,
+                format!(r#"toml key "{key}" does not exist"#),
+            )
+            .with_path(manifest_path);
+        };
+        let name = get_str(doc.get("name"), "name")?.ok_or_else(|| missing_key("name"))?;
+        let table = get_str(doc.get("table"), "table")?.ok_or_else(|| missing_key("table"))?;
+        let database = match get_str(doc.get("database"), "database")? {
+            Some(database) => PathBuf::from(database),
+            None => manifest_path.with_extension("db"),
+        };
+        // 相对路径相对于清单所在的目录
+        let database = match manifest_path.parent() {
+            Some(dir) if database.is_relative() => dir.join(database),
+            _ => database,
+        };
+        let columns = doc
+            .get("columns")
+            .ok_or_else(|| missing_key("columns"))?
+            .as_table_like()
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidValue,
+                    r#"the value of "columns" is not a table"#,
+                )
+                .with_path(manifest_path)
+            })?;
+        let columns = ColumnMapping {
+            word: get_str(columns.get("word"), "columns.word")?
+                .ok_or_else(|| missing_key("columns.word"))?,
+            phonetic: get_str(columns.get("phonetic"), "columns.phonetic")?,
+            sense: get_str(columns.get("sense"), "columns.sense")?,
+            definition: get_str(columns.get("definition"), "columns.definition")?,
+            translation: get_str(columns.get("translation"), "columns.translation")?,
+        };
+        return Ok(Manifest {
+            name,
+            source_language: get_str(doc.get("source-language"), "source-language")?,
+            target_language: get_str(doc.get("target-language"), "target-language")?,
+            database,
+            table,
+            columns,
+        });
+    }
+}
+
+/// 以 SQLite 数据库存储的词典，由清单指定表名和列的映射
+pub struct SqliteDictionary {
+    info: DictionaryInfo,
+    conn: Mutex<Connection>,
+    /// 查询词头的 SQL 语句
+    sql: String,
+}
+
+impl SqliteDictionary {
+    /// 按清单打开词典，并检查清单中的表和列是否存在
+    pub fn open(id: impl Into<String>, manifest_path: impl AsRef<Path>) -> Result<Self> {
+        fn inner(id: String, manifest_path: &Path) -> Result<SqliteDictionary> {
+            let manifest = Manifest::read(manifest_path)?;
+            fn quote(identifier: &str) -> String {
+                return format!("\"{}\"", identifier.replace('"', "\"\""));
+            }
+            fn column(name: &Option<String>) -> String {
+                return name.as_deref().map_or("null".to_string(), quote);
+            }
+            let columns = &manifest.columns;
+            let sql = format!(
+                "select {}, {}, {}, {}, {} from {} where {} = ?1 collate nocase order by rowid",
+                quote(&columns.word),
+                column(&columns.phonetic),
+                column(&columns.sense),
+                column(&columns.definition),
+                column(&columns.translation),
+                quote(&manifest.table),
+                quote(&columns.word),
+            );
+            let conn = dict::open_connection(&manifest.database)?;
+            // 预编译一次，使表名或列名错误在加载时就能发现
+            conn.prepare_cached(&sql).map_err(|e| {
+                Error::invalid_dictionary("dictionary does not match its manifest")
+                    .with_path(&manifest.database)
+                    .with_detail(e)
+            })?;
+            return Ok(SqliteDictionary {
+                info: DictionaryInfo {
+                    id,
+                    name: manifest.name,
+                    source_language: manifest.source_language,
+                    target_language: manifest.target_language,
+                    builtin: false,
+                },
+                conn: Mutex::new(conn),
+                sql,
+            });
+        }
+        return inner(id.into(), manifest_path.as_ref());
+    }
+}
+
+impl Dictionary for SqliteDictionary {
+    fn info(&self) -> &DictionaryInfo {
+        return &self.info;
+    }
+
+    fn search(&self, word: &str) -> Result<Vec<DictionaryEntry>> {
+        let id = &self.info.id;
+        let conn = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut stmt = conn.prepare_cached(&self.sql).map_err(|e| {
+            Error::sqlite(
+                format!("failed to prepare SQL statement for {id} search"),
+                e,
+            )
+        })?;
+        let mut rows = stmt
+            .query([word])
+            .map_err(|e| Error::sqlite(format!("failed to query {id}"), e))?;
+        let mut entries = vec![];
+        while let Some(row) = rows.next().map_err(|e| {
+            Error::sqlite(format!("failed to get next row from {id} search result"), e)
+        })? {
+            let get = |index: usize| -> Result<Option<String>> {
+                return row.get(index).map_err(|e| {
+                    Error::sqlite(format!("failed to get column {index} of {id}"), e)
+                });
+            };
+            let phonetic = get(1)?;
+            let sense = get(2)?;
+            entries.push(DictionaryEntry {
+                word: get(0)?.unwrap_or_default(),
+                phonetics: phonetics::parse_phonetics(phonetic.as_deref()),
+                phonetic,
+                grammar: grammar::parse_grammar(sense.as_deref(), None),
+                sense,
+                definition: get(3)?,
+                translation: get(4)?,
+                html: None,
+                user_provided: false,
+            });
+        }
+        return Ok(entries);
+    }
+}
+
+/// 从 `dictionaries/` 目录中加载的词典，包括带清单的 SQLite 词典和 MDict 词典
+#[derive(Default)]
+pub struct DictionaryRegistry {
+    /// 按 id 排序
+    dictionaries: Vec<Box<dyn Dictionary>>,
+}
+
+impl DictionaryRegistry {
+    /// 加载目录中的所有词典，目录不存在时返回空的注册表
+    ///
+    /// 单个词典加载失败不影响其他词典，失败的原因在第二个返回值中给出。
+    pub fn load(dir: impl AsRef<Path>) -> (Self, Vec<Error>) {
+        fn inner(dir: &Path) -> (DictionaryRegistry, Vec<Error>) {
+            let mut registry = DictionaryRegistry::default();
+            let mut errors = vec![];
+            let read_dir = match std::fs::read_dir(dir) {
+                Ok(read_dir) => read_dir,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (registry, errors),
+                Err(e) => {
+                    errors.push(Error::io("failed to read directory", e).with_path(dir));
+                    return (registry, errors);
+                }
+            };
+            // 词典清单（.toml）和 MDict 词典（.mdx），.mdd 资源文件随 .mdx 一起加载
+            let mut paths = vec![];
+            for entry in read_dir {
+                match entry {
+                    Ok(entry) => {
+                        let path = entry.path();
+                        if path.extension().is_some_and(|ext| {
+                            ext.eq_ignore_ascii_case("toml") || ext.eq_ignore_ascii_case("mdx")
+                        }) {
+                            paths.push(path);
+                        }
+                    }
+                    Err(e) => errors.push(Error::io("failed to read directory", e).with_path(dir)),
+                }
+            }
+            paths.sort();
+            for path in paths {
+                let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    errors.push(
+                        Error::invalid_dictionary("dictionary has an invalid file name")
+                            .with_path(&path),
+                    );
+                    continue;
+                };
+                if BUILTIN_DICTIONARY_IDS.contains(&id) || id == STARDICT_ID {
+                    errors.push(
+                        Error::invalid_dictionary(format!("dictionary uses reserved id {id}"))
+                            .with_path(&path),
+                    );
+                    continue;
+                }
+                if registry.get(id).is_some() {
+                    errors.push(
+                        Error::invalid_dictionary(format!(
+                            "dictionary uses id {id}, which is already in use"
+                        ))
+                        .with_path(&path),
+                    );
+                    continue;
+                }
+                let dictionary: Result<Box<dyn Dictionary>> = if path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+                {
+                    SqliteDictionary::open(id, &path).map(|d| Box::new(d) as _)
+                } else {
+                    MdxDictionary::open(id, &path).map(|d| Box::new(d) as _)
+                };
+                match dictionary {
+                    Ok(dictionary) => registry.dictionaries.push(dictionary),
+                    Err(e) => errors.push(e.with_path(&path)),
+                }
+            }
+            return (registry, errors);
+        }
+        return inner(dir.as_ref());
+    }
+
+    pub fn get(&self, id: &str) -> Option<&dyn Dictionary> {
+        return self
+            .dictionaries
+            .iter()
+            .find(|dictionary| dictionary.info().id == id)
+            .map(|dictionary| dictionary.as_ref());
+    }
+
+    pub fn infos(&self) -> Vec<DictionaryInfo> {
+        return self
+            .dictionaries
+            .iter()
+            .map(|dictionary| dictionary.info().clone())
+            .collect();
+    }
+}
@@ -0,0 +1,651 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use super::dict::{Dictionary, DictionaryEntry, DictionaryInfo};
+use super::error::{Error, ErrorKind, Result};
+use super::phonetics;
+
+/// 配置文件中选择的 StarDict 词典的 id
+pub const STARDICT_ID: &str = "stardict";
+
+/// 找到 StarDict 词典的 .ifo 文件，`path` 可以是 .ifo 文件本身或其所在的目录
+pub fn find_ifo(path: impl AsRef<Path>) -> Result<PathBuf> {
+    fn inner(path: &Path) -> Result<PathBuf> {
+        if !path.is_dir() {
+            if !path.is_file() {
+                return Err(
+                    Error::new(ErrorKind::NotFound, "StarDict path does not exist").with_path(path),
+                );
+            }
+            return Ok(path.to_path_buf());
+        }
+        let mut ifo_paths = vec![];
+        let read_dir = std::fs::read_dir(path)
+            .map_err(|e| Error::io("failed to read directory", e).with_path(path))?;
+        for entry in read_dir {
+            let entry =
+                entry.map_err(|e| Error::io("failed to read directory", e).with_path(path))?;
+            let entry_path = entry.path();
+            if entry_path.extension().is_some_and(|ext| ext == "ifo") {
+                ifo_paths.push(entry_path);
+            }
+        }
+        return match ifo_paths.len() {
+            0 => Err(
+                Error::new(ErrorKind::NotFound, "no .ifo file found in directory").with_path(path),
+            ),
+            1 => Ok(ifo_paths.pop().expect("unexpected empty")),
+            _ => Err(Error::invalid_dictionary(
+                "more than one .ifo file found in directory, please specify the .ifo file",
+            )
+            .with_path(path)),
+        };
+    }
+    return inner(path.as_ref());
+}
+
+/// .ifo 文件中的信息
+#[derive(Debug, Clone)]
+struct Ifo {
+    book_name: String,
+    word_count: usize,
+    syn_word_count: Option<usize>,
+    /// .idx 中的偏移量是否为 64 位
+    offset_64: bool,
+    /// 若所有词条的字段类型相同，则在此给出，词条中不再包含类型字符
+    same_type_sequence: Option<Vec<u8>>,
+}
+
+/// .idx 中的一个词条
+struct IdxEntry {
+    word: String,
+    offset: u64,
+    size: u32,
+}
+
+/// StarDict 词典（.ifo / .idx / .dict.dz / .syn）
+///
+/// 打开时将 .idx 和 .syn 读入内存，按 StarDict 的排序规则二分查找；
+/// 词条内容在查询时从 .dict 或 .dict.dz 中读取，.dict.dz 按 dictzip 的块随机访问。
+pub struct StarDict {
+    info: DictionaryInfo,
+    ifo_path: PathBuf,
+    /// 按 StarDict 的规则排序
+    entries: Vec<IdxEntry>,
+    /// 同义词及其在 `entries` 中的下标，按 StarDict 的规则排序
+    synonyms: Vec<(String, u32)>,
+    same_type_sequence: Option<Vec<u8>>,
+    data: DictData,
+}
+
+impl StarDict {
+    /// 打开 .ifo 文件所在的词典，其余文件与 .ifo 同名
+    pub fn open(id: impl Into<String>, ifo_path: impl AsRef<Path>) -> Result<Self> {
+        fn inner(id: String, ifo_path: &Path) -> Result<StarDict> {
+            let ifo = read_ifo(ifo_path)?;
+            let base = ifo_path.with_extension("");
+            let with_suffix = |suffix: &str| -> PathBuf {
+                let mut path = base.clone().into_os_string();
+                path.push(suffix);
+                return PathBuf::from(path);
+            };
+
+            let idx_path = with_suffix(".idx");
+            let idx_gz_path = with_suffix(".idx.gz");
+            let idx = if idx_path.is_file() {
+                read_file(&idx_path)?
+            } else if idx_gz_path.is_file() {
+                gunzip(&read_file(&idx_gz_path)?).map_err(|e| e.with_path(&idx_gz_path))?
+            } else {
+                return Err(Error::new(ErrorKind::NotFound, "index file does not exist")
+                    .with_path(&idx_path));
+            };
+            let entries = parse_idx(&idx, ifo.offset_64).map_err(|e| e.with_path(&idx_path))?;
+            if entries.len() != ifo.word_count {
+                return Err(Error::invalid_dictionary(format!(
+                    "{} contains {} words, but wordcount in {} is {}",
+                    idx_path.display(),
+                    entries.len(),
+                    ifo_path.display(),
+                    ifo.word_count
+                )));
+            }
+
+            let syn_path = with_suffix(".syn");
+            let mut synonyms = if syn_path.is_file() {
+                let synonyms = parse_syn(&read_file(&syn_path)?, entries.len())
+                    .map_err(|e| e.with_path(&syn_path))?;
+                if ifo
+                    .syn_word_count
+                    .is_some_and(|count| count != synonyms.len())
+                {
+                    return Err(Error::invalid_dictionary(format!(
+                        "{} contains {} synonyms, but synwordcount in {} does not match",
+                        syn_path.display(),
+                        synonyms.len(),
+                        ifo_path.display()
+                    )));
+                }
+                synonyms
+            } else {
+                vec![]
+            };
+
+            let entries = ensure_sorted(entries, &mut synonyms);
+
+            let dict_dz_path = with_suffix(".dict.dz");
+            let dict_path = with_suffix(".dict");
+            let data = if dict_dz_path.is_file() {
+                DictData::open_dictzip(&dict_dz_path)?
+            } else if dict_path.is_file() {
+                let file = File::open(&dict_path)
+                    .map_err(|e| Error::io("failed to open dict file", e).with_path(&dict_path))?;
+                DictData::Plain(Mutex::new(file))
+            } else {
+                return Err(Error::new(ErrorKind::NotFound, "dict file does not exist")
+                    .with_path(&dict_dz_path));
+            };
+
+            return Ok(StarDict {
+                info: DictionaryInfo {
+                    id,
+                    name: ifo.book_name,
+                    source_language: None,
+                    target_language: None,
+                    builtin: false,
+                },
+                ifo_path: ifo_path.to_path_buf(),
+                entries,
+                synonyms,
+                same_type_sequence: ifo.same_type_sequence,
+                data,
+            });
+        }
+        return inner(id.into(), ifo_path.as_ref());
+    }
+
+    pub fn ifo_path(&self) -> &Path {
+        return &self.ifo_path;
+    }
+
+    /// 在 `entries` 中找出与 `word` 相同（忽略 ASCII 大小写）的词条下标
+    fn find(&self, word: &str) -> Vec<usize> {
+        let mut indices = equal_range(&self.entries, word, |entry| &entry.word).collect::<Vec<_>>();
+        for i in equal_range(&self.synonyms, word, |(synonym, _)| synonym) {
+            let index = self.synonyms[i].1 as usize;
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+        return indices;
+    }
+
+    fn read_entry(&self, entry: &IdxEntry) -> Result<DictionaryEntry> {
+        let data = self
+            .data
+            .read(entry.offset, entry.size as usize)
+            .map_err(|e| Error {
+                message: format!("failed to read {}: {}", entry.word, e.message),
+                ..e
+            })?;
+        let fields =
+            parse_fields(&data, self.same_type_sequence.as_deref()).map_err(|e| Error {
+                message: format!("failed to parse {}: {}", entry.word, e.message),
+                ..e
+            })?;
+        let mut result = DictionaryEntry {
+            word: entry.word.clone(),
+            ..Default::default()
+        };
+        fn append(target: &mut Option<String>, text: String) {
+            match target {
+                Some(existing) => {
+                    existing.push('\n');
+                    existing.push_str(&text);
+                }
+                None => *target = Some(text),
+            }
+        }
+        for (kind, content) in fields {
+            let text = String::from_utf8_lossy(content).into_owned();
+            match kind {
+                b't' | b'y' => append(&mut result.phonetic, text),
+                b'h' | b'g' | b'x' => append(&mut result.html, text),
+                b'm' | b'l' | b'k' | b'w' | b'n' => append(&mut result.definition, text),
+                // 资源列表以及音频、图片等二进制字段暂不支持
+                _ => {}
+            }
+        }
+        result.phonetics = phonetics::parse_phonetics(result.phonetic.as_deref());
+        return Ok(result);
+    }
+}
+
+impl Dictionary for StarDict {
+    fn info(&self) -> &DictionaryInfo {
+        return &self.info;
+    }
+
+    fn search(&self, word: &str) -> Result<Vec<DictionaryEntry>> {
+        let mut entries = vec![];
+        for index in self.find(word.trim()) {
+            entries.push(self.read_entry(&self.entries[index])?);
+        }
+        return Ok(entries);
+    }
+}
+
+/// .dict 文件的内容
+enum DictData {
+    Plain(Mutex<File>),
+    /// dictzip 格式：gzip 文件的每个块可以单独解压
+    DictZip {
+        file: Mutex<File>,
+        /// 每个块解压后的大小（最后一块可能较小）
+        chunk_len: usize,
+        /// 每个块在文件中的位置，最后一个元素为数据的结束位置
+        chunk_offsets: Vec<u64>,
+        /// 最近一次解压的块
+        last_chunk: Mutex<Option<(usize, Vec<u8>)>>,
+    },
+    /// 不含 dictzip 信息的普通 gzip 文件，只能整体解压
+    Memory(Vec<u8>),
+}
+
+impl DictData {
+    fn open_dictzip(path: &Path) -> Result<Self> {
+        let mut file = File::open(path)
+            .map_err(|e| Error::io("failed to open dict file", e).with_path(path))?;
+        // gzip 头部不会太大，读取开头的部分用于解析
+        let mut head = vec![];
+        (&mut file)
+            .take(1 << 16)
+            .read_to_end(&mut head)
+            .map_err(|e| Error::io("failed to read dict file", e).with_path(path))?;
+        let header = parse_gzip_header(&head).map_err(|e| e.with_path(path))?;
+        let Some((chunk_len, chunk_sizes)) = header.chunks else {
+            let data = gunzip(&read_file(path)?).map_err(|e| e.with_path(path))?;
+            return Ok(DictData::Memory(data));
+        };
+        let mut chunk_offsets = Vec::with_capacity(chunk_sizes.len() + 1);
+        let mut offset = header.data_offset as u64;
+        chunk_offsets.push(offset);
+        for size in chunk_sizes {
+            offset += size as u64;
+            chunk_offsets.push(offset);
+        }
+        return Ok(DictData::DictZip {
+            file: Mutex::new(file),
+            chunk_len,
+            chunk_offsets,
+            last_chunk: Mutex::new(None),
+        });
+    }
+
+    /// 读取解压后的数据中 `[offset, offset + size)` 的部分
+    fn read(&self, offset: u64, size: usize) -> Result<Vec<u8>> {
+        match self {
+            DictData::Plain(file) => {
+                let mut file = file.lock().unwrap_or_else(PoisonError::into_inner);
+                file.seek(SeekFrom::Start(offset))
+                    .map_err(|e| Error::io("failed to seek in dict file", e))?;
+                let mut data = vec![0; size];
+                file.read_exact(&mut data)
+                    .map_err(|e| Error::io("failed to read dict file", e))?;
+                return Ok(data);
+            }
+            DictData::Memory(data) => {
+                let start = usize::try_from(offset)
+                    .map_err(|_| Error::invalid_dictionary("offset is too large"))?;
+                return data
+                    .get(start..start + size)
+                    .map(|data| data.to_vec())
+                    .ok_or_else(|| Error::invalid_dictionary("entry is out of range"));
+            }
+            DictData::DictZip {
+                file,
+                chunk_len,
+                chunk_offsets,
+                last_chunk,
+            } => {
+                let mut data = Vec::with_capacity(size);
+                let end = offset + size as u64;
+                let mut position = offset;
+                let mut last_chunk = last_chunk.lock().unwrap_or_else(PoisonError::into_inner);
+                while position < end {
+                    let chunk_index = (position / *chunk_len as u64) as usize;
+                    if chunk_index + 1 >= chunk_offsets.len() {
+                        return Err(Error::invalid_dictionary("entry is out of range"));
+                    }
+                    let cached =
+                        matches!(last_chunk.as_ref(), Some((index, _)) if *index == chunk_index);
+                    if !cached {
+                        let start = chunk_offsets[chunk_index];
+                        let compressed_size = (chunk_offsets[chunk_index + 1] - start) as usize;
+                        let mut compressed = vec![0; compressed_size];
+                        {
+                            let mut file = file.lock().unwrap_or_else(PoisonError::into_inner);
+                            file.seek(SeekFrom::Start(start))
+                                .map_err(|e| Error::io("failed to seek in dict file", e))?;
+                            file.read_exact(&mut compressed)
+                                .map_err(|e| Error::io("failed to read dict file", e))?;
+                        }
+                        let chunk = inflate_chunk(&compressed, *chunk_len)?;
+                        *last_chunk = Some((chunk_index, chunk));
+                    }
+                    let (_, chunk) = last_chunk.as_ref().expect("unexpected None");
+                    let chunk_start = chunk_index as u64 * *chunk_len as u64;
+                    let from = (position - chunk_start) as usize;
+                    let to = ((end - chunk_start) as usize).min(chunk.len());
+                    if from >= to {
+                        return Err(Error::invalid_dictionary("entry is out of range"));
+                    }
+                    data.extend_from_slice(&chunk[from..to]);
+                    position = chunk_start + to as u64;
+                }
+                return Ok(data);
+            }
+        }
+    }
+}
+
+struct GzipHeader {
+    /// 压缩数据的起始位置
+    data_offset: usize,
+    /// dictzip 的块大小及每块压缩后的大小
+    chunks: Option<(usize, Vec<u16>)>,
+}
+
+fn parse_gzip_header(data: &[u8]) -> Result<GzipHeader> {
+    const FHCRC: u8 = 0x02;
+    const FEXTRA: u8 = 0x04;
+    const FNAME: u8 = 0x08;
+    const FCOMMENT: u8 = 0x10;
+    if data.len() < 10 || data[0] != 0x1f || data[1] != 0x8b || data[2] != 8 {
+        return Err(Error::invalid_dictionary("not a gzip file"));
+    }
+    let flags = data[3];
+    let mut position = 10;
+    let mut chunks = None;
+    let get = |from: usize, to: usize| -> Result<&[u8]> {
+        return data
+            .get(from..to)
+            .ok_or_else(|| Error::invalid_dictionary("gzip header is truncated"));
+    };
+    if flags & FEXTRA != 0 {
+        let extra_len =
+            u16::from_le_bytes(get(position, position + 2)?.try_into().unwrap()) as usize;
+        position += 2;
+        let extra = get(position, position + extra_len)?;
+        position += extra_len;
+        // 子字段：2 字节 ID，2 字节长度，之后为数据
+        let mut i = 0;
+        while i + 4 <= extra.len() {
+            let len = u16::from_le_bytes([extra[i + 2], extra[i + 3]]) as usize;
+            let field = extra
+                .get(i + 4..i + 4 + len)
+                .ok_or_else(|| Error::invalid_dictionary("gzip extra field is truncated"))?;
+            if &extra[i..i + 2] == b"RA" && field.len() >= 6 {
+                // 版本号，块大小，块数，之后为每块压缩后的大小
+                let chunk_len = u16::from_le_bytes([field[2], field[3]]) as usize;
+                let chunk_count = u16::from_le_bytes([field[4], field[5]]) as usize;
+                let sizes = field
+                    .get(6..6 + chunk_count * 2)
+                    .ok_or_else(|| Error::invalid_dictionary("dictzip chunk table is truncated"))?
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                    .collect();
+                if chunk_len == 0 {
+                    return Err(Error::invalid_dictionary("dictzip chunk length is 0"));
+                }
+                chunks = Some((chunk_len, sizes));
+            }
+            i += 4 + len;
+        }
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let len = data[position.min(data.len())..]
+                .iter()
+                .position(|&byte| byte == 0)
+                .ok_or_else(|| Error::invalid_dictionary("gzip header is truncated"))?;
+            position += len + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        position += 2;
+    }
+    return Ok(GzipHeader {
+        data_offset: position,
+        chunks,
+    });
+}
+
+/// 解压 dictzip 的一个块（以 full flush 结尾的 raw deflate 数据）
+fn inflate_chunk(compressed: &[u8], chunk_len: usize) -> Result<Vec<u8>> {
+    let mut decompress = flate2::Decompress::new(false);
+    let mut chunk = Vec::with_capacity(chunk_len);
+    decompress
+        .decompress_vec(compressed, &mut chunk, flate2::FlushDecompress::Sync)
+        .map_err(|e| {
+            Error::invalid_dictionary("failed to decompress dictzip chunk").with_detail(e)
+        })?;
+    return Ok(chunk);
+}
+
+fn gunzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decompressed = vec![];
+    flate2::read::MultiGzDecoder::new(data)
+        .read_to_end(&mut decompressed)
+        .map_err(|e| Error::invalid_dictionary("failed to decompress gzip data").with_detail(e))?;
+    return Ok(decompressed);
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    return std::fs::read(path).map_err(|e| Error::io("failed to read file", e).with_path(path));
+}
+
+fn read_ifo(ifo_path: &Path) -> Result<Ifo> {
+    let text = std::fs::read_to_string(ifo_path)
+        .map_err(|e| Error::io("failed to read .ifo file", e).with_path(ifo_path))?;
+    let mut lines = text.lines();
+    if lines
+        .next()
+        .map(|line| line.trim_start_matches('\u{feff}').trim())
+        != Some("StarDict's dict ifo file")
+    {
+        return Err(Error::invalid_dictionary("not a StarDict .ifo file").with_path(ifo_path));
+    }
+    let options: HashMap<&str, &str> = lines
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect();
+    let parse_count = |key: &str| -> Result<Option<usize>> {
+        return options
+            .get(key)
+            .map(|value| {
+                value.parse::<usize>().map_err(|_| {
+                    Error::invalid_dictionary(format!("{key} is not a number")).with_path(ifo_path)
+                })
+            })
+            .transpose();
+    };
+    let version = options.get("version").copied().unwrap_or_default();
+    return Ok(Ifo {
+        book_name: options
+            .get("bookname")
+            .map(|name| name.to_string())
+            .ok_or_else(|| Error::invalid_dictionary("bookname is missing").with_path(ifo_path))?,
+        word_count: parse_count("wordcount")?
+            .ok_or_else(|| Error::invalid_dictionary("wordcount is missing").with_path(ifo_path))?,
+        syn_word_count: parse_count("synwordcount")?,
+        // idxoffsetbits 只在 3.0.0 版本中有效
+        offset_64: version == "3.0.0" && options.get("idxoffsetbits") == Some(&"64"),
+        same_type_sequence: options
+            .get("sametypesequence")
+            .filter(|sequence| !sequence.is_empty())
+            .map(|sequence| sequence.as_bytes().to_vec()),
+    });
+}
+
+/// 解析 .idx：每个词条为以 0 结尾的词头、偏移量（32 或 64 位，大端序）、大小（32 位，大端序）
+fn parse_idx(data: &[u8], offset_64: bool) -> Result<Vec<IdxEntry>> {
+    let offset_size = if offset_64 { 8 } else { 4 };
+    let mut entries = vec![];
+    let mut position = 0;
+    while position < data.len() {
+        let word_len = data[position..]
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or_else(|| Error::invalid_dictionary("word is not terminated"))?;
+        let word = String::from_utf8_lossy(&data[position..position + word_len]).into_owned();
+        position += word_len + 1;
+        let numbers = data
+            .get(position..position + offset_size + 4)
+            .ok_or_else(|| Error::invalid_dictionary("index is truncated"))?;
+        let offset = if offset_64 {
+            u64::from_be_bytes(numbers[..8].try_into().unwrap())
+        } else {
+            u32::from_be_bytes(numbers[..4].try_into().unwrap()) as u64
+        };
+        let size = u32::from_be_bytes(numbers[offset_size..].try_into().unwrap());
+        position += offset_size + 4;
+        entries.push(IdxEntry { word, offset, size });
+    }
+    return Ok(entries);
+}
+
+/// 解析 .syn：每个同义词为以 0 结尾的词头、在 .idx 中的下标（32 位，大端序）
+fn parse_syn(data: &[u8], word_count: usize) -> Result<Vec<(String, u32)>> {
+    let mut synonyms = vec![];
+    let mut position = 0;
+    while position < data.len() {
+        let word_len = data[position..]
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or_else(|| Error::invalid_dictionary("synonym is not terminated"))?;
+        let word = String::from_utf8_lossy(&data[position..position + word_len]).into_owned();
+        position += word_len + 1;
+        let index = data
+            .get(position..position + 4)
+            .ok_or_else(|| Error::invalid_dictionary("synonym file is truncated"))?;
+        let index = u32::from_be_bytes(index.try_into().unwrap());
+        if index as usize >= word_count {
+            return Err(Error::invalid_dictionary(format!(
+                "synonym {word} refers to a word out of range"
+            )));
+        }
+        position += 4;
+        synonyms.push((word, index));
+    }
+    return Ok(synonyms);
+}
+
+/// 解析词条的各个字段，返回（类型字符，内容）
+///
+/// 小写类型的字段为以 0 结尾的文本，大写类型的字段以 32 位大小开头；
+/// 若给出了 `sametypesequence`，则字段不含类型字符，且最后一个字段不含结尾的 0 或大小。
+fn parse_fields<'a>(
+    data: &'a [u8],
+    same_type_sequence: Option<&[u8]>,
+) -> Result<Vec<(u8, &'a [u8])>> {
+    let mut fields = vec![];
+    let mut position = 0;
+    let mut read_field = |kind: u8, last: bool, position: &mut usize| -> Result<()> {
+        let rest = &data[(*position).min(data.len())..];
+        if last {
+            fields.push((kind, rest));
+            *position = data.len();
+        } else if kind.is_ascii_lowercase() {
+            let len = rest
+                .iter()
+                .position(|&byte| byte == 0)
+                .unwrap_or(rest.len());
+            fields.push((kind, &rest[..len]));
+            *position += len + 1;
+        } else {
+            let size = rest
+                .get(..4)
+                .ok_or_else(|| Error::invalid_dictionary("field size is truncated"))?;
+            let size = u32::from_be_bytes(size.try_into().unwrap()) as usize;
+            let content = rest
+                .get(4..4 + size)
+                .ok_or_else(|| Error::invalid_dictionary("field is truncated"))?;
+            fields.push((kind, content));
+            *position += 4 + size;
+        }
+        return Ok(());
+    };
+    match same_type_sequence {
+        Some(sequence) => {
+            for (i, &kind) in sequence.iter().enumerate() {
+                read_field(kind, i + 1 == sequence.len(), &mut position)?;
+            }
+        }
+        None => {
+            while position < data.len() {
+                let kind = data[position];
+                position += 1;
+                read_field(kind, false, &mut position)?;
+            }
+        }
+    }
+    return Ok(fields);
+}
+
+/// StarDict 的排序规则：先忽略 ASCII 大小写比较，相同时再按字节比较
+fn stardict_cmp(a: &str, b: &str) -> Ordering {
+    return ascii_case_insensitive_cmp(a, b).then_with(|| a.cmp(b));
+}
+
+fn ascii_case_insensitive_cmp(a: &str, b: &str) -> Ordering {
+    let a = a.bytes().map(|byte| byte.to_ascii_lowercase());
+    let b = b.bytes().map(|byte| byte.to_ascii_lowercase());
+    return a.cmp(b);
+}
+
+/// 在按 StarDict 规则排序的列表中二分查找与 `word` 相同（忽略 ASCII 大小写）的元素
+fn equal_range<'a, T>(
+    items: &'a [T],
+    word: &'a str,
+    key: impl Fn(&T) -> &str + 'a,
+) -> impl Iterator<Item = usize> + 'a {
+    let start = items.partition_point(|item| ascii_case_insensitive_cmp(key(item), word).is_lt());
+    return (start..items.len())
+        .take_while(move |&i| ascii_case_insensitive_cmp(key(&items[i]), word).is_eq());
+}
+
+/// 确保词条和同义词按 StarDict 的规则排序，以便二分查找（个别词典的排序并不严格）
+fn ensure_sorted(entries: Vec<IdxEntry>, synonyms: &mut [(String, u32)]) -> Vec<IdxEntry> {
+    if !synonyms
+        .windows(2)
+        .all(|pair| stardict_cmp(&pair[0].0, &pair[1].0).is_le())
+    {
+        synonyms.sort_by(|a, b| stardict_cmp(&a.0, &b.0));
+    }
+    if entries
+        .windows(2)
+        .all(|pair| stardict_cmp(&pair[0].word, &pair[1].word).is_le())
+    {
+        return entries;
+    }
+    let mut order: Vec<usize> = (0..entries.len()).collect();
+    order.sort_by(|&a, &b| stardict_cmp(&entries[a].word, &entries[b].word));
+    // 同义词中的下标需要随之更新
+    let mut new_indices = vec![0u32; entries.len()];
+    for (new_index, &old_index) in order.iter().enumerate() {
+        new_indices[old_index] = new_index as u32;
+    }
+    for (_, index) in synonyms.iter_mut() {
+        *index = new_indices[*index as usize];
+    }
+    let mut entries: Vec<Option<IdxEntry>> = entries.into_iter().map(Some).collect();
+    return order
+        .into_iter()
+        .map(|i| entries[i].take().expect("unexpected None"))
+        .collect();
+}
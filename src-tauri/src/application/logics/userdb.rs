@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use super::error::{Error, Result};
+
+/// 用户数据库的文件名，与只读的 dict.db 分开存放
+pub const USER_DB_FILE: &str = "user.db";
+
+/// 各版本用户数据库的建表语句，第 `i` 项将数据库从版本 `i` 升级到版本 `i + 1`
+const MIGRATIONS: &[&str] = &[
+    "create table history (
+        id integer primary key,
+        time integer not null,
+        word text not null,
+        base text,
+        sentence text
+    );
+    create index history_time on history (time);
+    create index history_base on history (base);",
+    "create table known_words (
+        word text primary key,
+        time integer not null
+    );",
+    "create table user_entries (
+        id integer primary key,
+        word text not null,
+        phonetic text,
+        sense text,
+        en_def text,
+        cn_def text,
+        notes text,
+        replace_builtin integer not null default 0,
+        time integer not null
+    );
+    create index user_entries_word on user_entries (word collate nocase);",
+];
+
+/// 当前时间的 Unix 时间戳（毫秒），用户数据库中的时间都使用此格式
+pub fn now_millis() -> i64 {
+    return std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64);
+}
+
+/// 打开（必要时创建）用户数据库，并升级到当前版本
+pub fn open_user_db(user_db_path: impl AsRef<Path>) -> Result<Connection> {
+    fn inner(user_db_path: &Path) -> Result<Connection> {
+        if let Some(dir) = user_db_path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| Error::io("failed to create directory", e).with_path(dir))?;
+        }
+        let mut conn = Connection::open(user_db_path).map_err(|e| {
+            Error::sqlite("failed to open user database", e).with_path(user_db_path)
+        })?;
+        migrate(&mut conn).map_err(|e| e.with_path(user_db_path))?;
+        return Ok(conn);
+    }
+    return inner(user_db_path.as_ref());
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let version: usize = conn
+        .query_row("pragma user_version", [], |row| row.get(0))
+        .map_err(|e| Error::sqlite("failed to read user database version", e))?;
+    if version > MIGRATIONS.len() {
+        return Err(Error::internal(format!(
+            "user database version {version} is newer than this version of the app supports"
+        )));
+    }
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn
+            .transaction()
+            .map_err(|e| Error::sqlite("failed to begin user database migration", e))?;
+        tx.execute_batch(migration).map_err(|e| {
+            Error::sqlite(format!("failed to migrate user database to {}", i + 1), e)
+        })?;
+        tx.pragma_update(None, "user_version", i + 1)
+            .map_err(|e| Error::sqlite("failed to update user database version", e))?;
+        tx.commit()
+            .map_err(|e| Error::sqlite("failed to commit user database migration", e))?;
+    }
+    return Ok(());
+}
@@ -1,203 +1,356 @@
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{RecvTimeoutError, Sender};
 use std::thread::JoinHandle;
 use std::time::Duration;
 
 use notify::RecursiveMode;
-use notify_debouncer_full::new_debouncer;
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
 
-pub fn show_in_explorer(path: impl AsRef<Path>) -> Result<(), String> {
+use super::error::{Error, ErrorKind, Result};
+
+pub fn show_in_explorer(path: impl AsRef<Path>) -> Result<()> {
     #[cfg(target_os = "windows")]
-    fn inner(path: &str) -> Result<(), String> {
+    fn inner(path: &str) -> Result<()> {
         use std::os::windows::process::CommandExt;
         const CREATE_NO_WINDOW: u32 = 0x08000000;
         std::process::Command::new("cmd")
             .args(&["/C", "explorer", "/select,", path])
             .creation_flags(CREATE_NO_WINDOW)
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| Error::io("failed to spawn process", e))?;
         return Ok(());
     }
 
     #[cfg(target_os = "macos")]
-    fn inner(path: &str) -> Result<(), String> {
+    fn inner(path: &str) -> Result<()> {
         std::process::Command::new("open")
             .args(&["-R", path])
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| Error::io("failed to spawn process", e))?;
         return Ok(());
     }
 
     #[cfg(target_os = "linux")]
-    fn inner(path: &str) -> Result<(), String> {
+    fn inner(path: &str) -> Result<()> {
         let dir_path = std::path::Path::new(path)
             .parent()
-            .ok_or("Failed to get parent directory")?
+            .ok_or_else(|| Error::internal("failed to get parent directory").with_path(path))?
             .to_str()
-            .ok_or("Parent directory is not valid utf-8")?;
+            .ok_or_else(|| {
+                Error::internal("parent directory is not valid utf-8").with_path(path)
+            })?;
         std::process::Command::new("xdg-open")
             .arg(dir_path)
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| Error::io("failed to spawn process", e))?;
         return Ok(());
     }
 
     #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-    fn inner(_: &str) -> Result<(), String> {
-        Err(format!(
-            "show_in_explorer is not implemented on this platform: {}",
-            std::env::consts::OS
+    fn inner(_: &str) -> Result<()> {
+        Err(Error::new(
+            ErrorKind::PlatformUnsupported,
+            format!(
+                "show_in_explorer is not implemented on this platform: {}",
+                std::env::consts::OS
+            ),
         ))
     }
 
+    let path = path.as_ref();
     let path = path
-        .as_ref()
         .to_str()
-        .ok_or("path is not valid utf-8")?
+        .ok_or_else(|| Error::internal("path is not valid utf-8").with_path(path))?
         .replace('/', std::path::MAIN_SEPARATOR_STR)
         .replace('\\', std::path::MAIN_SEPARATOR_STR);
     return inner(&path);
 }
 
-pub fn open_filepath(path: impl AsRef<Path>) -> Result<(), String> {
+pub fn open_filepath(path: impl AsRef<Path>) -> Result<()> {
     #[cfg(target_os = "windows")]
-    fn inner(path: &str) -> Result<(), String> {
+    fn inner(path: &str) -> Result<()> {
         use std::os::windows::process::CommandExt;
         const CREATE_NO_WINDOW: u32 = 0x08000000;
         std::process::Command::new("cmd")
             .args(&["/C", "start", "", path])
             .creation_flags(CREATE_NO_WINDOW)
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| Error::io("failed to spawn process", e))?;
         return Ok(());
     }
 
     #[cfg(target_os = "macos")]
-    fn inner(path: &str) -> Result<(), String> {
+    fn inner(path: &str) -> Result<()> {
         std::process::Command::new("open")
             .arg(path)
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| Error::io("failed to spawn process", e))?;
         return Ok(());
     }
 
     #[cfg(target_os = "linux")]
-    fn inner(path: &str) -> Result<(), String> {
+    fn inner(path: &str) -> Result<()> {
         std::process::Command::new("xdg-open")
             .arg(path)
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| Error::io("failed to spawn process", e))?;
         return Ok(());
     }
 
     #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-    fn inner(_: &str) -> Result<(), String> {
-        Err(format!(
-            "open_filepath is not implemented on this platform: {}",
-            std::env::consts::OS
+    fn inner(_: &str) -> Result<()> {
+        Err(Error::new(
+            ErrorKind::PlatformUnsupported,
+            format!(
+                "open_filepath is not implemented on this platform: {}",
+                std::env::consts::OS
+            ),
         ))
     }
 
+    let path = path.as_ref();
     let path = path
-        .as_ref()
         .to_str()
-        .ok_or("path is not valid utf-8")?
+        .ok_or_else(|| Error::internal("path is not valid utf-8").with_path(path))?
         .replace('/', std::path::MAIN_SEPARATOR_STR)
         .replace('\\', std::path::MAIN_SEPARATOR_STR);
     return inner(&path);
 }
 
-pub fn open_in_browser(url: &str) -> Result<(), String> {
+pub fn open_in_browser(url: &str) -> Result<()> {
     #[cfg(target_os = "windows")]
-    fn inner(url: &str) -> Result<(), String> {
+    fn inner(url: &str) -> Result<()> {
         use std::os::windows::process::CommandExt;
         const CREATE_NO_WINDOW: u32 = 0x08000000;
         std::process::Command::new("cmd")
             .args(&["/C", "start", "", url])
             .creation_flags(CREATE_NO_WINDOW)
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| Error::io("failed to spawn process", e))?;
         Ok(())
     }
 
     #[cfg(target_os = "macos")]
-    fn inner(url: &str) -> Result<(), String> {
+    fn inner(url: &str) -> Result<()> {
         std::process::Command::new("open")
             .arg(url)
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| Error::io("failed to spawn process", e))?;
         Ok(())
     }
 
     #[cfg(target_os = "linux")]
-    fn inner(url: &str) -> Result<(), String> {
+    fn inner(url: &str) -> Result<()> {
         std::process::Command::new("xdg-open")
             .arg(url)
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| Error::io("failed to spawn process", e))?;
         Ok(())
     }
 
     #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-    fn inner(_: &str) -> Result<(), String> {
-        Err(format!(
-            "open_in_browser is not implemented on this platform: {}",
-            std::env::consts::OS
+    fn inner(_: &str) -> Result<()> {
+        Err(Error::new(
+            ErrorKind::PlatformUnsupported,
+            format!(
+                "open_in_browser is not implemented on this platform: {}",
+                std::env::consts::OS
+            ),
         ))
     }
 
     inner(url)
 }
 
-pub fn current_exe_dir() -> Result<PathBuf, String> {
-    let exe_path = std::env::current_exe()
-        .map_err(|e| format!("failed to get current exe path: {}", e.to_string()))?;
-    let exe_dir = exe_path
-        .parent()
-        .ok_or("failed to get current exe directory")?;
+pub fn current_exe_dir() -> Result<PathBuf> {
+    let exe_path =
+        std::env::current_exe().map_err(|e| Error::io("failed to get current exe path", e))?;
+    let exe_dir = exe_path.parent().ok_or_else(|| {
+        Error::internal("failed to get current exe directory").with_path(&exe_path)
+    })?;
     return Ok(exe_dir.to_path_buf());
 }
 
+/// 原子地写入文件：先写入同一目录下的临时文件并刷新到磁盘，再重命名为目标文件
+///
+/// 写入中途崩溃时目标文件保持原样，不会留下不完整的内容；临时文件沿用目标文件的权限。
+pub fn write_file_atomically(
+    path: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+) -> std::io::Result<()> {
+    fn inner(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+        let file_name = path.file_name().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name")
+        })?;
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}.tmp",
+            file_name.to_string_lossy(),
+            std::process::id()
+        ));
+        let write_temp = || -> std::io::Result<()> {
+            let mut file = std::fs::File::create(&temp_path)?;
+            if let Ok(metadata) = std::fs::metadata(path) {
+                file.set_permissions(metadata.permissions())?;
+            }
+            file.write_all(contents)?;
+            file.sync_all()?;
+            return Ok(());
+        };
+        if let Err(e) = write_temp().and_then(|()| std::fs::rename(&temp_path, path)) {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e);
+        }
+        // 刷新所在目录，使重命名本身也写入磁盘
+        #[cfg(unix)]
+        if let Some(dir) = path.parent().and_then(|dir| std::fs::File::open(dir).ok()) {
+            let _ = dir.sync_all();
+        }
+        return Ok(());
+    }
+    return inner(path.as_ref(), contents.as_ref());
+}
+
+/// 发送给文件监视线程的消息
+enum WatchMessage {
+    Events(DebounceEventResult),
+    Stop,
+}
+
+type WatchDebouncer = Debouncer<notify::RecommendedWatcher, RecommendedCache>;
+
+/// 文件监视器，调用 `stop` 或被 drop 时停止监视并等待监视线程结束
+pub struct FileWatcher {
+    sender: Sender<WatchMessage>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FileWatcher {
+    pub fn stop(self) {
+        drop(self);
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        let _ = self.sender.send(WatchMessage::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// 监视文件所在的目录（而不是文件本身），以便文件被删除后重新创建、
+/// 或者编辑器先写入临时文件再重命名覆盖原文件时仍然能收到通知
+fn watch_dir(debouncer: &mut WatchDebouncer, dir: &Path) -> Result<()> {
+    let _ = debouncer.unwatch(dir);
+    debouncer
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|e| {
+            Error::new(ErrorKind::Watcher, "failed to watch file change")
+                .with_path(dir)
+                .with_detail(e)
+        })?;
+    return Ok(());
+}
+
+/// 监视文件的变化，文件被修改、创建或被其他文件重命名覆盖后调用 `on_change`
+///
+/// 监视出错时调用 `on_error` 并重新开始监视；所在目录被删除时每隔 `timeout` 尝试重新监视，
+/// 重新监视成功且文件存在时调用一次 `on_change`。
 pub fn watch_file_change(
     file_path: impl AsRef<Path>,
     on_change: impl Fn() + Send + 'static,
-    on_error: impl Fn() + Send + 'static,
+    on_error: impl Fn(Error) + Send + 'static,
     timeout: Duration,
-) -> Result<JoinHandle<()>, String> {
+) -> Result<FileWatcher> {
     fn inner(
         file_path: &Path,
         on_change: impl Fn() + Send + 'static,
-        on_error: impl Fn() + Send + 'static,
+        on_error: impl Fn(Error) + Send + 'static,
         timeout: Duration,
-    ) -> Result<JoinHandle<()>, String> {
+    ) -> Result<FileWatcher> {
+        let (Some(dir), Some(file_name)) = (file_path.parent(), file_path.file_name()) else {
+            return Err(
+                Error::internal("watched path has no parent directory or file name")
+                    .with_path(file_path),
+            );
+        };
+        let (dir, file_name) = (dir.to_path_buf(), file_name.to_os_string());
         let (sender, receiver) = std::sync::mpsc::channel();
-        let mut debouncer = new_debouncer(timeout, None, sender)
-            .map_err(|e| format!("failed to create file watcher debouncer: {e}"))?;
-        debouncer
-            .watch(Path::new(file_path), RecursiveMode::NonRecursive)
-            .map_err(|e| {
-                format!(
-                    "failed to watch file change for {}: {e}",
-                    file_path.display()
-                )
-            })?;
+        let event_sender = sender.clone();
+        let mut debouncer = new_debouncer(timeout, None, move |result| {
+            let _ = event_sender.send(WatchMessage::Events(result));
+        })
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Watcher,
+                "failed to create file watcher debouncer",
+            )
+            .with_detail(e)
+        })?;
+        watch_dir(&mut debouncer, &dir).map_err(|e| e.with_path(file_path))?;
         let file_path = file_path.to_path_buf();
-        let join_handle = std::thread::spawn(move || {
-            let _keep_debouncer_alive = debouncer;
-            for res in receiver {
-                match res {
-                    Ok(_events) => {
-                        if file_path.try_exists().is_ok_and(|exists| exists) {
+        let thread = std::thread::spawn(move || {
+            let exists = |path: &Path| path.try_exists().is_ok_and(|exists| exists);
+            let mut armed = true;
+            loop {
+                let message = match receiver.recv_timeout(timeout) {
+                    Ok(message) => message,
+                    Err(RecvTimeoutError::Timeout) => {
+                        // 目录被删除后不再有事件，需要定期检查目录是否被重新创建
+                        if !armed && exists(&dir) && watch_dir(&mut debouncer, &dir).is_ok() {
+                            armed = true;
+                            if exists(&file_path) {
+                                on_change();
+                            }
+                        }
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                let result = match message {
+                    WatchMessage::Events(result) => result,
+                    WatchMessage::Stop => break,
+                };
+                match result {
+                    Ok(events) => {
+                        let touched = events.iter().any(|event| {
+                            event
+                                .paths
+                                .iter()
+                                .any(|path| path.file_name() == Some(file_name.as_os_str()))
+                        });
+                        if touched && exists(&file_path) {
                             on_change();
                         }
                     }
-                    Err(_errors) => {
-                        on_error();
+                    Err(errors) => {
+                        let detail = errors
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join("; ");
+                        on_error(
+                            Error::new(ErrorKind::Watcher, "file watcher reported errors")
+                                .with_path(&file_path)
+                                .with_detail(detail),
+                        );
+                        if let Err(e) = watch_dir(&mut debouncer, &dir) {
+                            on_error(e);
+                            armed = false;
+                        }
                     }
                 }
+                if armed && !exists(&dir) {
+                    armed = false;
+                }
             }
-            unreachable!("watcher loop exited");
         });
-        return Ok(join_handle);
+        return Ok(FileWatcher {
+            sender,
+            thread: Some(thread),
+        });
     }
     return inner(file_path.as_ref(), on_change, on_error, timeout);
 }
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use super::dict;
+use super::error::{Error, Result};
+use super::userdb::now_millis;
+
+/// 词元的分类
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenStatus {
+    /// 原型在熟词表中
+    Known,
+    /// 词典中有此单词，但不在熟词表中
+    Unknown,
+    /// 词典中没有此单词（包括标点、数字等非单词的词元）
+    NotInDictionary,
+}
+
+/// 句子中一个词元的分类结果
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenClass {
+    token: String,
+    /// 熟词表中使用的原型，词典中没有此单词时为 `None`
+    base: Option<String>,
+    status: TokenStatus,
+}
+
+/// 熟词表按原型存储，原型未知时使用单词本身，统一为小写
+fn vocabulary_key(dict_conn: &Connection, word: &str) -> Result<Option<String>> {
+    let word = word.trim();
+    if word.is_empty() {
+        return Ok(None);
+    }
+    let base = dict::resolve_word_base(dict_conn, word)?;
+    return Ok(Some(base.as_deref().unwrap_or(word).to_lowercase()));
+}
+
+/// 将单词的原型加入熟词表，返回实际加入的原型（已在表中的不重复加入）
+pub fn add_known_words(
+    conn: &Connection,
+    dict_conn: &Connection,
+    words: &[String],
+) -> Result<Vec<String>> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| Error::sqlite("failed to begin transaction for known words", e))?;
+    let time = now_millis();
+    let mut added = vec![];
+    {
+        let mut stmt = tx
+            .prepare_cached("insert or ignore into known_words (word, time) values (?1, ?2)")
+            .map_err(|e| Error::sqlite("failed to prepare SQL statement for known words", e))?;
+        for word in words {
+            let Some(key) = vocabulary_key(dict_conn, word)? else {
+                continue;
+            };
+            let inserted = stmt
+                .execute((&key, time))
+                .map_err(|e| Error::sqlite("failed to add known word", e))?;
+            if inserted > 0 {
+                added.push(key);
+            }
+        }
+    }
+    tx.commit()
+        .map_err(|e| Error::sqlite("failed to commit known words", e))?;
+    return Ok(added);
+}
+
+/// 将单词的原型从熟词表中移除，返回实际移除的原型
+pub fn remove_known_words(
+    conn: &Connection,
+    dict_conn: &Connection,
+    words: &[String],
+) -> Result<Vec<String>> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| Error::sqlite("failed to begin transaction for known words", e))?;
+    let mut removed = vec![];
+    {
+        let mut stmt = tx
+            .prepare_cached("delete from known_words where word = ?1")
+            .map_err(|e| Error::sqlite("failed to prepare SQL statement for known words", e))?;
+        for word in words {
+            let Some(key) = vocabulary_key(dict_conn, word)? else {
+                continue;
+            };
+            let deleted = stmt
+                .execute([&key])
+                .map_err(|e| Error::sqlite("failed to remove known word", e))?;
+            // 原型推断可能与加入时不同（如词典更新后），此时按单词本身移除
+            let lowercase = word.trim().to_lowercase();
+            let deleted = if deleted == 0 && lowercase != key {
+                stmt.execute([&lowercase])
+                    .map_err(|e| Error::sqlite("failed to remove known word", e))?
+            } else {
+                deleted
+            };
+            if deleted > 0 {
+                removed.push(key);
+            }
+        }
+    }
+    tx.commit()
+        .map_err(|e| Error::sqlite("failed to commit known words", e))?;
+    return Ok(removed);
+}
+
+/// 熟词表中的所有单词，按字母顺序排列
+pub fn list_known_words(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn
+        .prepare_cached("select word from known_words order by word")
+        .map_err(|e| Error::sqlite("failed to prepare SQL statement for known words", e))?;
+    let words = stmt
+        .query_map([], |row| row.get(0))
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<String>>>())
+        .map_err(|e| Error::sqlite("failed to query known words", e))?;
+    return Ok(words);
+}
+
+/// 从纯文本文件导入熟词，每行一个单词（也可以用空白或逗号分隔），`#` 开头的行为注释
+///
+/// 返回新加入的原型。
+pub fn import_known_words(
+    conn: &Connection,
+    dict_conn: &Connection,
+    path: impl AsRef<Path>,
+) -> Result<Vec<String>> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| Error::io("failed to read known words file", e).with_path(path))?;
+    let words: Vec<String> = text
+        .lines()
+        .map(|line| line.trim_start_matches('\u{feff}'))
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect();
+    return add_known_words(conn, dict_conn, &words);
+}
+
+/// 将熟词表导出为纯文本文件，每行一个单词，返回导出的单词数
+pub fn export_known_words(conn: &Connection, path: impl AsRef<Path>) -> Result<usize> {
+    let path = path.as_ref();
+    let words = list_known_words(conn)?;
+    let mut text = words.join("\n");
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    std::fs::write(path, text)
+        .map_err(|e| Error::io("failed to write known words file", e).with_path(path))?;
+    return Ok(words.len());
+}
+
+/// 将句子中的词元分为熟词、生词和词典中没有的词
+pub fn classify_tokens(
+    conn: &Connection,
+    dict_conn: &Connection,
+    tokens: &[String],
+) -> Result<Vec<TokenClass>> {
+    let mut stmt = conn
+        .prepare_cached("select exists(select 1 from known_words where word = ?1)")
+        .map_err(|e| Error::sqlite("failed to prepare SQL statement for known words", e))?;
+    let mut classified: HashMap<String, TokenClass> = HashMap::new();
+    let mut results = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let lowercase = token.to_lowercase();
+        if let Some(class) = classified.get(&lowercase) {
+            results.push(TokenClass {
+                token: token.clone(),
+                ..class.clone()
+            });
+            continue;
+        }
+        let class = if !token.chars().any(char::is_alphabetic) {
+            TokenClass {
+                token: token.clone(),
+                base: None,
+                status: TokenStatus::NotInDictionary,
+            }
+        } else {
+            let base = dict::resolve_word_base(dict_conn, token)?;
+            if base.is_none() && !dict::is_headword(dict_conn, token)? {
+                TokenClass {
+                    token: token.clone(),
+                    base: None,
+                    status: TokenStatus::NotInDictionary,
+                }
+            } else {
+                let key = base.as_deref().unwrap_or(token).to_lowercase();
+                let known: bool = stmt
+                    .query_row([&key], |row| row.get(0))
+                    .map_err(|e| Error::sqlite("failed to query known words", e))?;
+                TokenClass {
+                    token: token.clone(),
+                    base: Some(key),
+                    status: if known {
+                        TokenStatus::Known
+                    } else {
+                        TokenStatus::Unknown
+                    },
+                }
+            }
+        };
+        classified.insert(lowercase, class.clone());
+        results.push(class);
+    }
+    return Ok(results);
+}
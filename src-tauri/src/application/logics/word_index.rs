@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+
+use rusqlite::Connection;
+
+use super::error::{Error, Result};
+
+/// 单词出现在哪些表中
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct Sources {
+    pub collins: bool,
+    pub oxford: bool,
+    pub forms: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Suggestion {
+    word: String,
+    /// 编辑距离（已按键盘位置和常见拼写错误加权）
+    distance: f32,
+    in_collins: bool,
+    in_oxford: bool,
+}
+
+/// 词典中所有词头的内存索引，用于拼写建议
+///
+/// 在启动时构建一次，查询时在字典树上找出编辑距离不超过阈值的候选词，
+/// 再使用加权编辑距离排序。
+pub struct WordIndex {
+    /// 小写形式的词头，按字典序排列
+    keys: Vec<Vec<char>>,
+    /// 每个小写词头对应的原始写法（可能有多个大小写不同的写法）
+    spellings: Vec<Vec<(String, Sources)>>,
+    trie: Trie,
+}
+
+impl WordIndex {
+    pub fn build(conn: &Connection) -> Result<Self> {
+        let mut words: HashMap<String, Sources> = HashMap::new();
+        for table in ["collins", "oxford", "forms"] {
+            let mut stmt = conn
+                .prepare(&format!("select distinct word from {table}"))
+                .map_err(|e| {
+                    Error::sqlite(
+                        format!("failed to prepare SQL statement for {table} words"),
+                        e,
+                    )
+                })?;
+            let mut rows = stmt
+                .query([])
+                .map_err(|e| Error::sqlite(format!("failed to query {table} words"), e))?;
+            while let Some(row) = rows.next().map_err(|e| {
+                Error::sqlite(format!("failed to get next row from {table} words"), e)
+            })? {
+                let word: Option<String> = row
+                    .get(0)
+                    .map_err(|e| Error::sqlite("failed to get word", e))?;
+                let Some(word) = word else { continue };
+                let word = word.trim();
+                if word.is_empty() {
+                    continue;
+                }
+                let sources = words.entry(word.to_string()).or_default();
+                match table {
+                    "collins" => sources.collins = true,
+                    "oxford" => sources.oxford = true,
+                    _ => sources.forms = true,
+                }
+            }
+        }
+        return Ok(Self::from_words(words));
+    }
+
+    fn from_words(words: HashMap<String, Sources>) -> Self {
+        let mut grouped: HashMap<String, Vec<(String, Sources)>> = HashMap::new();
+        for (word, sources) in words {
+            grouped
+                .entry(word.to_lowercase())
+                .or_default()
+                .push((word, sources));
+        }
+        let mut grouped: Vec<(String, Vec<(String, Sources)>)> = grouped.into_iter().collect();
+        grouped.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut keys = Vec::with_capacity(grouped.len());
+        let mut spellings = Vec::with_capacity(grouped.len());
+        let mut trie = Trie::new();
+        for (key, mut words) in grouped {
+            words.sort_by(|a, b| a.0.cmp(&b.0));
+            let chars: Vec<char> = key.chars().collect();
+            trie.insert(keys.len() as u32, &chars);
+            keys.push(chars);
+            spellings.push(words);
+        }
+        return WordIndex {
+            keys,
+            spellings,
+            trie,
+        };
+    }
+
+    /// 返回与 `word` 最接近的词头，按加权编辑距离从小到大排序
+    pub fn suggest(&self, word: &str, limit: usize) -> Vec<Suggestion> {
+        let query: Vec<char> = word.trim().to_lowercase().chars().collect();
+        if query.is_empty() || limit == 0 {
+            return vec![];
+        }
+        let max_distance = if query.len() <= 4 { 1 } else { 2 };
+        let mut candidates: Vec<(f32, usize, &str, Sources)> = vec![];
+        for index in self.trie.search(&query, max_distance) {
+            let key = &self.keys[index as usize];
+            let distance = weighted_distance(&query, key);
+            for (spelling, sources) in &self.spellings[index as usize] {
+                // 只有 forms 表中的词形权重稍低，优先推荐词典中的词头
+                let penalty = if sources.collins || sources.oxford {
+                    0.0
+                } else {
+                    0.1
+                };
+                candidates.push((distance + penalty, key.len(), spelling, *sources));
+            }
+        }
+        candidates.sort_by(|a, b| {
+            a.0.total_cmp(&b.0)
+                .then_with(|| a.1.abs_diff(query.len()).cmp(&b.1.abs_diff(query.len())))
+                .then_with(|| a.2.cmp(b.2))
+        });
+        return candidates
+            .into_iter()
+            .take(limit)
+            .map(|(distance, _, word, sources)| Suggestion {
+                word: word.to_string(),
+                distance,
+                in_collins: sources.collins,
+                in_oxford: sources.oxford,
+            })
+            .collect();
+    }
+}
+
+/// 小写词头组成的字典树
+struct Trie {
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Default)]
+struct TrieNode {
+    /// 子节点按字符升序排列
+    children: Vec<(char, u32)>,
+    /// 若某个词头在此结束，记录其在 `WordIndex::keys` 中的下标
+    key: Option<u32>,
+}
+
+impl Trie {
+    fn new() -> Self {
+        return Trie {
+            nodes: vec![TrieNode::default()],
+        };
+    }
+
+    /// 插入一个词头，词头需按升序插入以保证子节点有序
+    fn insert(&mut self, key: u32, chars: &[char]) {
+        let mut current = 0;
+        for &c in chars {
+            let existing = self.nodes[current]
+                .children
+                .iter()
+                .find(|(x, _)| *x == c)
+                .map(|(_, child)| *child);
+            current = match existing {
+                Some(child) => child as usize,
+                None => {
+                    let child = self.nodes.len() as u32;
+                    self.nodes.push(TrieNode::default());
+                    self.nodes[current].children.push((c, child));
+                    child as usize
+                }
+            };
+        }
+        self.nodes[current].key = Some(key);
+    }
+
+    /// 找出与 `query` 的编辑距离（Optimal String Alignment，交换相邻字母计为 1）
+    /// 不超过 `max_distance` 的所有词头
+    fn search(&self, query: &[char], max_distance: usize) -> Vec<u32> {
+        let mut found = vec![];
+        let mut rows: Vec<Vec<usize>> = vec![(0..=query.len()).collect()];
+        self.walk(0, None, query, max_distance, &mut rows, &mut found);
+        return found;
+    }
+
+    fn walk(
+        &self,
+        node: usize,
+        prev_char: Option<char>,
+        query: &[char],
+        max_distance: usize,
+        rows: &mut Vec<Vec<usize>>,
+        found: &mut Vec<u32>,
+    ) {
+        let depth = rows.len() - 1;
+        for &(c, child) in &self.nodes[node].children {
+            let mut row = vec![depth + 1; query.len() + 1];
+            for j in 1..=query.len() {
+                let prev_row = &rows[depth];
+                let cost = if query[j - 1] == c { 0 } else { 1 };
+                row[j] = (prev_row[j] + 1)
+                    .min(row[j - 1] + 1)
+                    .min(prev_row[j - 1] + cost);
+                if depth >= 1 && j >= 2 && Some(query[j - 1]) == prev_char && query[j - 2] == c {
+                    row[j] = row[j].min(rows[depth - 1][j - 2] + 1);
+                }
+            }
+            let child_node = &self.nodes[child as usize];
+            if let Some(key) = child_node.key {
+                if row[query.len()] <= max_distance {
+                    found.push(key);
+                }
+            }
+            if row.iter().min().is_some_and(|&min| min <= max_distance) {
+                rows.push(row);
+                self.walk(child as usize, Some(c), query, max_distance, rows, found);
+                rows.pop();
+            }
+        }
+    }
+}
+
+/// 加权的编辑距离（Optimal String Alignment）
+///
+/// - 键盘上相邻按键的替换、元音之间的替换、发音相近字母的替换代价较低；
+/// - 重复字母的插入或删除（如 "occured"/"occurred"）代价较低；
+/// - 相邻字母交换（如 "recieve"/"receive"）代价较低。
+fn weighted_distance(a: &[char], b: &[char]) -> f32 {
+    let (n, m) = (a.len(), b.len());
+    let mut d = vec![vec![0f32; m + 1]; n + 1];
+    for i in 1..=n {
+        d[i][0] = d[i - 1][0] + indel_cost(a, i - 1);
+    }
+    for j in 1..=m {
+        d[0][j] = d[0][j - 1] + indel_cost(b, j - 1);
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let substitution = d[i - 1][j - 1] + substitution_cost(a[i - 1], b[j - 1]);
+            let deletion = d[i - 1][j] + indel_cost(a, i - 1);
+            let insertion = d[i][j - 1] + indel_cost(b, j - 1);
+            let mut best = substitution.min(deletion).min(insertion);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(d[i - 2][j - 2] + 0.6);
+            }
+            d[i][j] = best;
+        }
+    }
+    return d[n][m];
+}
+
+/// 插入或删除 `word[i]` 的代价
+fn indel_cost(word: &[char], i: usize) -> f32 {
+    let doubled = (i > 0 && word[i - 1] == word[i]) || word.get(i + 1) == Some(&word[i]);
+    return if doubled { 0.4 } else { 1.0 };
+}
+
+fn substitution_cost(a: char, b: char) -> f32 {
+    if a == b {
+        return 0.0;
+    }
+    const SIMILAR_SOUNDS: &[(char, char)] = &[
+        ('c', 'k'),
+        ('c', 's'),
+        ('s', 'z'),
+        ('i', 'y'),
+        ('f', 'v'),
+        ('g', 'j'),
+    ];
+    if is_vowel(a) && is_vowel(b) {
+        return 0.6;
+    }
+    if SIMILAR_SOUNDS
+        .iter()
+        .any(|&(x, y)| (x, y) == (a, b) || (y, x) == (a, b))
+    {
+        return 0.6;
+    }
+    if keyboard_adjacent(a, b) {
+        return 0.5;
+    }
+    return 1.0;
+}
+
+fn is_vowel(c: char) -> bool {
+    return matches!(c, 'a' | 'e' | 'i' | 'o' | 'u');
+}
+
+/// QWERTY 键盘上两个字母是否相邻
+fn keyboard_adjacent(a: char, b: char) -> bool {
+    const ROWS: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+    fn position(c: char) -> Option<(i32, i32)> {
+        for (row, keys) in ROWS.iter().enumerate() {
+            if let Some(col) = keys.find(c) {
+                return Some((row as i32, col as i32));
+            }
+        }
+        return None;
+    }
+    let (Some((ra, ca)), Some((rb, cb))) = (position(a), position(b)) else {
+        return false;
+    };
+    // 相邻行之间错开半个键位，下一行的第 i 个键与上一行的第 i、i+1 个键相邻
+    return match rb - ra {
+        0 => (ca - cb).abs() == 1,
+        1 => cb == ca || cb == ca - 1,
+        -1 => cb == ca || cb == ca + 1,
+        _ => false,
+    };
+}
@@ -1,3 +1,4 @@
 pub mod config;
 pub mod dict;
 pub mod logics;
+pub mod user;
@@ -0,0 +1,256 @@
+use std::ops::DerefMut;
+use std::sync::{Mutex, PoisonError};
+
+use rusqlite::Connection;
+use tauri::{AppHandle, Manager};
+
+use super::dict::run_blocking;
+use super::logics;
+use super::logics::error::{Error, Result};
+use super::logics::history::{HistoryEntry, LookupCount};
+use super::logics::overlay::{UserEntry, UserEntryContent};
+use super::logics::pool::ConnectionPool;
+use super::logics::vocabulary::TokenClass;
+
+/// 用户数据库的路径，便携模式下位于 exe 旁边，否则位于应用数据目录
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct UserDbPath(pub String);
+
+impl UserDbPath {
+    pub fn new(
+        portable: bool,
+        path_resolver: &tauri::path::PathResolver<impl tauri::Runtime>,
+    ) -> Result<Self> {
+        let data_dir = if portable {
+            logics::utils::current_exe_dir()?
+        } else {
+            path_resolver.app_data_dir().map_err(|e| {
+                Error::internal("failed to resolve app data directory").with_detail(e)
+            })?
+        };
+        let user_db_path = data_dir.join(logics::userdb::USER_DB_FILE);
+        return Ok(UserDbPath(user_db_path.to_string_lossy().into_owned()));
+    }
+}
+
+/// 用户数据库的连接，第一次使用时打开（必要时创建）
+pub struct UserDb(pub Mutex<Option<Connection>>);
+
+impl UserDb {
+    pub fn new() -> Self {
+        return UserDb(Mutex::new(None));
+    }
+}
+
+/// 在当前线程中使用用户数据库
+pub fn use_user_db<T>(app: &AppHandle, f: impl FnOnce(&mut Connection) -> Result<T>) -> Result<T> {
+    let user_db = app.state::<UserDb>();
+    let mut guard = user_db.0.lock().unwrap_or_else(PoisonError::into_inner);
+    let conn = match guard.deref_mut() {
+        Some(conn) => conn,
+        None => {
+            let user_db_path = app.state::<UserDbPath>();
+            guard.insert(logics::userdb::open_user_db(&user_db_path.0)?)
+        }
+    };
+    return f(conn);
+}
+
+/// 在阻塞线程池中使用用户数据库
+async fn with_user_db<T, F>(app: AppHandle, f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&AppHandle, &mut Connection) -> Result<T> + Send + 'static,
+{
+    return run_blocking(app, move |app| {
+        return use_user_db(app, |conn| f(app, conn));
+    })
+    .await;
+}
+
+/// 查询单词在用户词典中的词条，用于与自带词典的查询结果合并
+///
+/// 用户数据库不可用时只输出错误并返回空列表，不影响自带词典的查询。
+/// 调用时不能持有 dict.db 的连接，否则可能与持有用户数据库、等待 dict.db 连接的命令互相等待。
+pub fn user_entries_for(app: &AppHandle, words: &[&str]) -> Vec<UserEntry> {
+    let result = use_user_db(app, |conn| {
+        let mut entries = vec![];
+        for word in words {
+            entries.extend(logics::overlay::search_user_entries(conn, word)?);
+        }
+        return Ok(entries);
+    });
+    return match result {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("failed to query user dictionary: {e}");
+            vec![]
+        }
+    };
+}
+
+/// 记录一次查询，未给出原型时从 dict.db 中查询原型
+#[tauri::command(rename_all = "snake_case")]
+pub async fn record_lookup(
+    word: String,
+    base: Option<String>,
+    sentence: Option<String>,
+    app: AppHandle,
+) -> Result<HistoryEntry> {
+    return with_user_db(app, move |app, conn| {
+        let base = match base {
+            Some(base) => Some(base),
+            None => {
+                let pool = app.state::<ConnectionPool>();
+                let dict_conn = pool.get()?;
+                logics::dict::resolve_word_base(&dict_conn, &word)?
+            }
+        };
+        return logics::history::record_lookup(conn, &word, base.as_deref(), sentence.as_deref());
+    })
+    .await;
+}
+
+/// 最近的查询记录，按时间从新到旧排列
+#[tauri::command(rename_all = "snake_case")]
+pub async fn recent_lookups(limit: Option<usize>, app: AppHandle) -> Result<Vec<HistoryEntry>> {
+    return with_user_db(app, move |_, conn| {
+        return logics::history::recent_lookups(conn, limit.unwrap_or(100));
+    })
+    .await;
+}
+
+/// 查询次数最多的单词（按原型合并）
+#[tauri::command(rename_all = "snake_case")]
+pub async fn most_looked_up(limit: Option<usize>, app: AppHandle) -> Result<Vec<LookupCount>> {
+    return with_user_db(app, move |_, conn| {
+        return logics::history::most_looked_up(conn, limit.unwrap_or(100));
+    })
+    .await;
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn clear_history(app: AppHandle) -> Result<()> {
+    return with_user_db(app, move |_, conn| {
+        return logics::history::clear_history(conn);
+    })
+    .await;
+}
+
+/// 将单词的原型加入熟词表，返回新加入的原型
+#[tauri::command(rename_all = "snake_case")]
+pub async fn add_known_words(words: Vec<String>, app: AppHandle) -> Result<Vec<String>> {
+    return with_user_db(app, move |app, conn| {
+        let pool = app.state::<ConnectionPool>();
+        let dict_conn = pool.get()?;
+        return logics::vocabulary::add_known_words(conn, &dict_conn, &words);
+    })
+    .await;
+}
+
+/// 将单词的原型从熟词表中移除，返回实际移除的原型
+#[tauri::command(rename_all = "snake_case")]
+pub async fn remove_known_words(words: Vec<String>, app: AppHandle) -> Result<Vec<String>> {
+    return with_user_db(app, move |app, conn| {
+        let pool = app.state::<ConnectionPool>();
+        let dict_conn = pool.get()?;
+        return logics::vocabulary::remove_known_words(conn, &dict_conn, &words);
+    })
+    .await;
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_known_words(app: AppHandle) -> Result<Vec<String>> {
+    return with_user_db(app, move |_, conn| {
+        return logics::vocabulary::list_known_words(conn);
+    })
+    .await;
+}
+
+/// 从纯文本文件导入熟词（每行一个单词），返回新加入的原型
+#[tauri::command(rename_all = "snake_case")]
+pub async fn import_known_words(path: String, app: AppHandle) -> Result<Vec<String>> {
+    return with_user_db(app, move |app, conn| {
+        let pool = app.state::<ConnectionPool>();
+        let dict_conn = pool.get()?;
+        return logics::vocabulary::import_known_words(conn, &dict_conn, &path);
+    })
+    .await;
+}
+
+/// 将熟词表导出为纯文本文件（每行一个单词），返回导出的单词数
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_known_words(path: String, app: AppHandle) -> Result<usize> {
+    return with_user_db(app, move |_, conn| {
+        return logics::vocabulary::export_known_words(conn, &path);
+    })
+    .await;
+}
+
+/// 将句子中的词元分为熟词（known）、生词（unknown）和词典中没有的词（not-in-dictionary）
+#[tauri::command(rename_all = "snake_case")]
+pub async fn classify_tokens(tokens: Vec<String>, app: AppHandle) -> Result<Vec<TokenClass>> {
+    return with_user_db(app, move |app, conn| {
+        let pool = app.state::<ConnectionPool>();
+        let dict_conn = pool.get()?;
+        return logics::vocabulary::classify_tokens(conn, &dict_conn, &tokens);
+    })
+    .await;
+}
+
+/// 用户词典中的所有词条
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_user_entries(app: AppHandle) -> Result<Vec<UserEntry>> {
+    return with_user_db(app, move |_, conn| {
+        return logics::overlay::list_user_entries(conn);
+    })
+    .await;
+}
+
+/// 向用户词典添加词条，`replace_builtin` 为 `true` 时此单词不再显示 collins、oxford 中的词条
+#[tauri::command(rename_all = "snake_case")]
+pub async fn add_user_entry(entry: UserEntryContent, app: AppHandle) -> Result<UserEntry> {
+    return with_user_db(app, move |_, conn| {
+        return logics::overlay::add_user_entry(conn, entry);
+    })
+    .await;
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn update_user_entry(
+    id: i64,
+    entry: UserEntryContent,
+    app: AppHandle,
+) -> Result<UserEntry> {
+    return with_user_db(app, move |_, conn| {
+        return logics::overlay::update_user_entry(conn, id, entry);
+    })
+    .await;
+}
+
+/// 删除用户词典中的词条，返回词条是否存在
+#[tauri::command(rename_all = "snake_case")]
+pub async fn delete_user_entry(id: i64, app: AppHandle) -> Result<bool> {
+    return with_user_db(app, move |_, conn| {
+        return logics::overlay::delete_user_entry(conn, id);
+    })
+    .await;
+}
+
+/// 从 JSON 文件导入用户词典，已有的相同词条不重复导入，返回实际导入的词条数
+#[tauri::command(rename_all = "snake_case")]
+pub async fn import_user_entries(path: String, app: AppHandle) -> Result<usize> {
+    return with_user_db(app, move |_, conn| {
+        return logics::overlay::import_user_entries(conn, &path);
+    })
+    .await;
+}
+
+/// 将用户词典导出为 JSON 文件，返回导出的词条数
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_user_entries(path: String, app: AppHandle) -> Result<usize> {
+    return with_user_db(app, move |_, conn| {
+        return logics::overlay::export_user_entries(conn, &path);
+    })
+    .await;
+}
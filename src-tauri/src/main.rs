@@ -1,9 +1,6 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::sync::Mutex;
-
-use rusqlite::Connection;
 use tauri::Manager;
 
 mod application;
@@ -21,25 +18,90 @@ fn main() {
                 portable.0,
                 app.path(),
             )?);
-            app.manage(application::config::IsWatching::new());
-            app.manage(application::dict::DictPath::new(portable.0, app.path())?);
-            app.manage(Mutex::new(None::<Connection>));
+            app.manage(application::config::ConfigWatcher::new());
+            let dict_path = application::dict::DictPath::new(portable.0, app.path())?;
+            if let Err(e) = application::logics::dict::validate_dict_db(&dict_path.0) {
+                println!("dict.db is not usable: {e}");
+            }
+            let dictionaries_path = application::dict::DictionariesPath::new(&dict_path)?;
+            app.manage(application::dict::Dictionaries::new());
+            app.manage(dictionaries_path);
+            app.manage(application::logics::pool::ConnectionPool::new(
+                &dict_path.0,
+                application::dict::CONNECTION_POOL_SIZE,
+            ));
+            app.manage(application::logics::cache::DictCache::new(
+                &dict_path.0,
+                application::dict::DICT_CACHE_CAPACITY,
+            ));
+            app.manage(application::dict::Frequency::load(&dict_path));
+            app.manage(dict_path);
+            app.manage(application::dict::SelectedStarDict::new());
+            app.manage(application::dict::DefinitionIndexPath::new(
+                portable.0,
+                app.path(),
+            )?);
+            app.manage(application::dict::DefinitionIndex::new());
+            app.manage(application::dict::SuggestionIndex::new());
+            app.manage(application::user::UserDbPath::new(portable.0, app.path())?);
+            app.manage(application::user::UserDb::new());
+            application::dict::spawn_dictionaries_loader(app.handle());
+            application::dict::spawn_suggestion_index_builder(app.handle());
+            if let Err(e) = application::dict::start_dict_watcher(app.handle()) {
+                println!("failed to watch dict.db: {e}");
+            }
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             application::config::read_config,
+            application::config::recreate_config,
             application::config::commit_config,
+            application::config::list_profiles,
+            application::config::create_profile,
+            application::config::rename_profile,
+            application::config::delete_profile,
+            application::config::switch_profile,
             application::config::config_path,
             application::config::is_portable,
             application::config::show_in_explorer,
             application::config::open_filepath,
             application::config::open_in_browser,
             application::config::start_config_watcher,
+            application::config::stop_config_watcher,
             application::config::rust_in_release,
+            application::dict::check_dict_db,
             application::dict::search_collins,
             application::dict::search_oxford,
             application::dict::get_word_base,
+            application::dict::guess_word_bases,
+            application::dict::get_word_forms,
+            application::dict::lookup_sentence,
+            application::dict::dict_cache_stats,
+            application::dict::word_levels,
+            application::dict::search_definitions,
+            application::dict::autocomplete,
+            application::dict::suggest_words,
+            application::dict::list_dictionaries,
+            application::dict::search_dictionary,
+            application::dict::get_dictionary_resource,
+            application::dict::reload_dictionaries,
             application::dict::sanitize_filename,
+            application::user::record_lookup,
+            application::user::recent_lookups,
+            application::user::most_looked_up,
+            application::user::clear_history,
+            application::user::add_known_words,
+            application::user::remove_known_words,
+            application::user::list_known_words,
+            application::user::import_known_words,
+            application::user::export_known_words,
+            application::user::classify_tokens,
+            application::user::list_user_entries,
+            application::user::add_user_entry,
+            application::user::update_user_entry,
+            application::user::delete_user_entry,
+            application::user::import_user_entries,
+            application::user::export_user_entries,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
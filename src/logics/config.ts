@@ -1,20 +1,34 @@
 import * as api from '../tauri-api';
 
-import { invoke } from './utils';
+import { invoke, BackendError, formatIssue, type BackendErrorPayload, type BackendIssue } from './utils';
 
 interface ConfigModel {
     ankiConnectURL: string;
     deckName: string;
     modelName: string;
+    stardictBundle: string;
 }
 
-const CONFIG_KEYS = ['ankiConnectURL', 'deckName', 'modelName'] as const;
+/** 后端返回的配置，除可修改的配置项外还包括配置方案的信息 */
+interface ConfigData extends ConfigModel {
+    /** 当前使用的配置方案名 */
+    activeProfile: string;
+    /** 所有配置方案名，按在配置文件中的顺序排列 */
+    profiles: string[];
+    /** 配置文件内容的标识，修改配置文件时传回，文件已被其他程序修改时后端拒绝写入 */
+    revision: string;
+    /** 不影响使用的问题，如拼错的键、已用默认值代替的无效值 */
+    warnings: BackendIssue[];
+}
+
+const CONFIG_KEYS = ['ankiConnectURL', 'deckName', 'modelName', 'stardictBundle'] as const;
 
 /** 配置项的默认值 */
 export const CONFIG_DEFAULTS: Record<keyof ConfigModel, string> = {
     ankiConnectURL: 'http://localhost:8765',
     deckName: '划词助手默认牌组',
     modelName: '划词助手默认单词模板',
+    stardictBundle: '',
 };
 
 export class Config implements ConfigModel {
@@ -28,8 +42,10 @@ export class Config implements ConfigModel {
     public deckName!: string;
     /** 划词结果使用的笔记模板名 */
     public modelName!: string;
+    /** StarDict 词典的 .ifo 文件路径，为空表示不使用 */
+    public stardictBundle!: string;
     /** 存储配置项的对象 */
-    private config: ConfigModel;
+    private config: ConfigData;
     /** 被修改过的配置项 */
     private modified: Partial<ConfigModel>;
     // Config 对象被设计为始终存活的全局单例，因此不需要取消事件监听
@@ -38,7 +54,7 @@ export class Config implements ConfigModel {
     /** 'config-watcher-error' 事件对应的取消监听函数 */
     public __unlistenConfigWatcherError?: () => void;
 
-    private constructor(config: ConfigModel, path: string, portable: boolean) {
+    private constructor(config: ConfigData, path: string, portable: boolean) {
         this.config = config;
         this.modified = {};
         this.path = path;
@@ -64,19 +80,83 @@ export class Config implements ConfigModel {
         });
     }
 
+    /** 当前使用的配置方案名 */
+    public get activeProfile(): string {
+        return this.config.activeProfile;
+    }
+
+    /** 所有配置方案名 */
+    public get profiles(): readonly string[] {
+        return this.config.profiles;
+    }
+
+    /** 读取配置文件时发现的不影响使用的问题 */
+    public get warnings(): readonly BackendIssue[] {
+        return this.config.warnings;
+    }
+
+    /** 创建配置方案，`copyFrom` 为空时各配置项使用默认值，否则复制该方案的配置项 */
+    public async createProfile(name: string, copyFrom?: string) {
+        await this.commit();
+        this.replace(await invoke<ConfigData>('create_profile', {
+            name,
+            copy_from: copyFrom ?? null,
+            revision: this.config.revision,
+        }));
+    }
+
+    public async renameProfile(oldName: string, newName: string) {
+        await this.commit();
+        this.replace(await invoke<ConfigData>('rename_profile', {
+            old_name: oldName,
+            new_name: newName,
+            revision: this.config.revision,
+        }));
+    }
+
+    /** 删除配置方案，删除的是当前使用的方案时切换到剩下的第一个方案 */
+    public async deleteProfile(name: string) {
+        await this.commit();
+        this.replace(await invoke<ConfigData>('delete_profile', { name, revision: this.config.revision }));
+    }
+
+    /** 切换配置方案，切换前先保存对当前方案的修改 */
+    public async switchProfile(name: string) {
+        await this.commit();
+        this.replace(await invoke<ConfigData>('switch_profile', { name, revision: this.config.revision }));
+    }
+
+    private replace(config: ConfigData) {
+        this.config = config;
+        this.modified = {};
+    }
+
+    /**
+     * 保存修改过的配置项。
+     * 配置文件在读取之后被其他程序修改时抛出 `ConfigConflict` 错误，修改保留到重新加载配置为止。
+     */
     public async commit() {
         if (Object.keys(this.modified).length === 0) {
             return;
         }
-        await invoke('commit_config', { modified: this.modified, config_path: this.path });
+        this.config.revision = await invoke<string>('commit_config', {
+            modified: this.modified,
+            revision: this.config.revision,
+            config_path: this.path,
+        });
         this.modified = {};
     }
 
     public async reload() {
         const newConfig = await Config.load();
-        if (CONFIG_KEYS.some(key => this.config[key] !== newConfig.config[key])) {
-            this.config = newConfig.config;
-            this.modified = {};
+        const profilesChanged = this.config.activeProfile !== newConfig.config.activeProfile
+            || this.config.profiles.join('\n') !== newConfig.config.profiles.join('\n');
+        if (profilesChanged || CONFIG_KEYS.some(key => this.config[key] !== newConfig.config[key])) {
+            this.replace(newConfig.config);
+        } else {
+            // 只有注释等不影响配置项的内容被修改，保留未保存的修改，之后可以正常保存
+            this.config.revision = newConfig.config.revision;
+            this.config.warnings = newConfig.config.warnings;
         }
     }
 
@@ -88,16 +168,53 @@ export class Config implements ConfigModel {
         return startConfigWatcher(this);
     }
 
+    /**
+     * 停止配置文件监视器。
+     * Return true if the watcher is stopped, false if it's not running.
+     */
+    public async stopWatcher(): Promise<boolean> {
+        return stopConfigWatcher();
+    }
+
     public static async load(): Promise<Config> {
         const [config_path, cfg, portable] = await Promise.all([
             invoke<string>('config_path'),
-            invoke<ConfigModel>('read_config'),
+            readConfig(),
             invoke<boolean>('is_portable')
         ]);
+        for (const warning of cfg.warnings) {
+            console.warn(`${config_path}: ${formatIssue(warning)}`);
+        }
         return new Config(cfg, config_path, portable);
     }
 }
 
+/** 读取配置文件，配置文件不存在时询问用户是否用模板重新创建 */
+async function readConfig(): Promise<ConfigData> {
+    try {
+        return await invoke<ConfigData>('read_config');
+    } catch (error) {
+        if (!(error instanceof BackendError) || error.kind !== 'ConfigMissing') {
+            throw error;
+        }
+        const recreate = await api.dialog.ask(`配置文件 ${error.path ?? ''} 不存在，是否使用默认配置重新创建？`, {
+            title: '配置文件不存在',
+            kind: 'warning',
+            okLabel: '重新创建配置',
+            cancelLabel: '取消',
+        });
+        if (!recreate) {
+            throw error;
+        }
+        return await invoke<ConfigData>('recreate_config');
+    }
+}
+
+/** 配置文件中的所有配置方案名 */
+export async function listProfiles(): Promise<string[]> {
+    return await invoke<string[]>('list_profiles');
+}
+
 export async function showInExplorer(path: string) {
     await invoke('show_in_explorer', { path });
 }
@@ -127,9 +244,17 @@ export async function startConfigWatcher(config: Config): Promise<boolean> {
     }
     if (config.__unlistenConfigWatcherError == null) {
         // 监听 'config-watcher-error' 事件，以便在配置文件监视器出错时输出错误信息
-        config.__unlistenConfigWatcherError = await api.event.listen('config-watcher-error', () => {
-            console.error('Config watcher error');
+        config.__unlistenConfigWatcherError = await api.event.listen<BackendErrorPayload>('config-watcher-error', event => {
+            console.error('Config watcher error:', new BackendError(event.payload));
         });
     }
     return newWatcherStarted;
 }
+
+/**
+ * 停止配置文件监视器，之后可以用 `startConfigWatcher` 重新启动。
+ * Return true if the watcher is stopped, false if it's not running.
+ */
+export async function stopConfigWatcher(): Promise<boolean> {
+    return await invoke<boolean>('stop_config_watcher');
+}
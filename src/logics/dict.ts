@@ -1,26 +1,72 @@
-import { invoke, sanitizeFilename } from './utils';
+import { invoke, sanitizeFilename, BackendError, type BackendErrorPayload } from './utils';
 import * as youdao from './youdao';
 import * as iciba from './iciba';
 import { MD5 } from './md5';
 
 export { makeYoudaoDictVoiceUrl } from './youdao';
 
+/** 由 phonetic 拆分出的音标，不含两侧的斜线或方括号 */
+export interface Phonetics {
+    /** 英式音标 */
+    uk: string | null;
+    /** 美式音标 */
+    us: string | null;
+    /** 未标明口音的音标，以及同一口音的其他读法 */
+    other: string[];
+}
+
+export type PartOfSpeech =
+    'noun' | 'verb' | 'phrasal-verb' | 'modal-verb' | 'auxiliary-verb' | 'adjective' | 'adverb' |
+    'pronoun' | 'preposition' | 'conjunction' | 'determiner' | 'number' | 'quantifier' |
+    'exclamation' | 'phrase' | 'prefix' | 'suffix' | 'combining-form' | 'abbreviation' | 'symbol';
+
+export type Countability = 'countable' | 'uncountable' | 'variable' | 'singular' | 'plural' | 'proper';
+
+export type Transitivity = 'transitive' | 'intransitive' | 'both' | 'linking';
+
+/** 由 sense（及 oxford 的 ext）解析出的词性和语法标注 */
+export interface Grammar {
+    pos: PartOfSpeech | null;
+    countability: Countability | null;
+    transitivity: Transitivity | null;
+    /** 语体标签，如 informal、formal、literary */
+    registers: string[];
+    /** 地区标签，如 BrE、AmE、NAmE */
+    regions: string[];
+    /** 无法识别的标注，如 ~ sth、usually passive */
+    patterns: string[];
+    /** 原始文本 */
+    raw: string;
+}
+
 export interface CollinsItem {
     word: string;
     phonetic: string | null;
+    phonetics?: Phonetics | null;
     sense: string | null;
     enDef: string | null;
     cnDef: string | null;
+    grammar?: Grammar | null;
+    /** 是否来自用户词典 */
+    userProvided?: boolean;
+    /** 用户词典中的备注 */
+    notes?: string | null;
 }
 
 export interface OxfordItem {
     word: string;
     phrase: string | null;
     phonetic: string | null;
+    phonetics?: Phonetics | null;
     sense: string | null;
     ext: string | null;
     enDef: string | null;
     cnDef: string | null;
+    grammar?: Grammar | null;
+    /** 是否来自用户词典 */
+    userProvided?: boolean;
+    /** 用户词典中的备注 */
+    notes?: string | null;
 }
 
 /** 获取单词的原型 */
@@ -60,6 +106,119 @@ export async function searchOxford(word: string, autoConvert: boolean = true): P
     return items;
 }
 
+/** 单词的词频与难度 */
+export interface WordLevel {
+    /** 词频表中的词头（可能是查询词的原型） */
+    word: string;
+    /** 词频排名，越小越常用 */
+    rank: number | null;
+    /** CEFR 等级，如 'A1'、'B2' */
+    cefr: string | null;
+    /** 考试词表标签，如 'cet4'、'cet6'、'ielts'、'toefl' */
+    tags: string[];
+}
+
+export interface TokenLookup {
+    token: string;
+    /** 查询时使用的原型，为 null 时只查询了 token 本身 */
+    base: string | null;
+    collins: CollinsItem[];
+    oxford: OxfordItem[];
+    /** 词频与难度，没有词频数据时为 null */
+    level: WordLevel | null;
+}
+
+/** 一次性查询句子中所有词的原型及 Collins、Oxford 词条 */
+export async function lookupSentence(tokens: string[]): Promise<TokenLookup[]> {
+    return await invoke<TokenLookup[]>('lookup_sentence', { tokens });
+}
+
+/** collins、oxford 查询结果缓存的统计信息 */
+export interface CacheStats {
+    /** 每本词典最多缓存的单词数 */
+    capacity: number;
+    collinsEntries: number;
+    oxfordEntries: number;
+    hits: number;
+    misses: number;
+    /** 因 dict.db 被替换而清空缓存的次数 */
+    invalidations: number;
+}
+
+export async function dictCacheStats(): Promise<CacheStats> {
+    return await invoke<CacheStats>('dict_cache_stats');
+}
+
+/** 批量查询单词的词频与难度，没有词频数据或查不到单词时对应的结果为 null */
+export async function wordLevels(words: string[]): Promise<(WordLevel | null)[]> {
+    return await invoke<(WordLevel | null)[]>('word_levels', { words });
+}
+
+export interface DictionaryInfo {
+    id: string;
+    name: string;
+    /** 词头的语言，如 'en' */
+    sourceLanguage: string | null;
+    /** 释义的语言，如 'zh' */
+    targetLanguage: string | null;
+    /** 是否为 dict.db 中自带的词典（collins、oxford） */
+    builtin: boolean;
+}
+
+/** 各词典通用的词条格式 */
+export interface DictionaryEntry {
+    word: string;
+    phonetic: string | null;
+    phonetics?: Phonetics | null;
+    sense: string | null;
+    /** 源语言的释义 */
+    definition: string | null;
+    /** 目标语言的释义 */
+    translation: string | null;
+    /** HTML 格式的完整词条（如 MDict 词典） */
+    html: string | null;
+    grammar?: Grammar | null;
+    /** 是否来自用户词典 */
+    userProvided?: boolean;
+}
+
+/** 列出所有可用的词典，包括 dictionaries 目录中的词典 */
+export async function listDictionaries(): Promise<DictionaryInfo[]> {
+    return await invoke<DictionaryInfo[]>('list_dictionaries');
+}
+
+/** 在指定的词典中查询单词，与 Collins、Oxford 一样会同时查询单词的原型 */
+export async function searchDictionary(id: string, word: string, autoConvert: boolean = true): Promise<DictionaryEntry[]> {
+    const words = autoConvert ? await convertWord(word) : [word];
+    const promises = words.map(word => invoke<DictionaryEntry[]>('search_dictionary', { id, word }));
+    const results: DictionaryEntry[][] = await Promise.all(promises);
+    return results.flat();
+}
+
+/**
+ * 获取词典中引用的资源（如 MDict 词典 .mdd 文件中的图片、音频）
+ *
+ * @param path 资源在词典中的路径，如 `\\images\\cat.png` 或 `sound/cat.mp3`
+ */
+export async function getDictionaryResource(id: string, path: string): Promise<Uint8Array> {
+    const data = await invoke<ArrayBuffer>('get_dictionary_resource', { id, path });
+    return new Uint8Array(data);
+}
+
+/**
+ * 检查 dict.db 是否可用，不可用时抛出的错误会给出具体原因
+ * （如 dict.db 只是未下载的 Git LFS 指针文件、缺少表或列、文件被截断）
+ */
+export async function checkDictDb(): Promise<void> {
+    await invoke('check_dict_db');
+}
+
+/** 重新加载 dictionaries 目录中的词典，返回加载失败的原因 */
+export async function reloadDictionaries(): Promise<BackendError[]> {
+    const errors = await invoke<BackendErrorPayload[]>('reload_dictionaries');
+    return errors.map(error => new BackendError(error));
+}
+
 export interface YoudaoItem {
     word: string;
     phonetic: string | null;
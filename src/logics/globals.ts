@@ -5,8 +5,10 @@ import * as semver from 'semver';
 
 import { Config } from './config';
 import * as anki from './anki';
+import * as dict from './dict';
 import { AnkiService } from './anki';
 import * as utils from './utils';
+import { BackendError, type BackendErrorPayload } from './utils';
 import { typeAssertion } from './typing';
 
 /**
@@ -78,6 +80,11 @@ export async function getAnkiService(): Promise<AnkiService> {
 }
 // #endregion
 
+// #region dict
+/** dict.db 热重载的次数，每次重载成功后加 1，界面监听它以重新搜索 */
+export const dictRevision = ref(0);
+// #endregion
+
 // #region app version
 interface LatestAppInfo {
     version: string;
@@ -300,6 +307,33 @@ export async function initAtAppStart() {
         await api.dialog.message(String(error), { title: '配置文件读取失败', kind: 'error' });
         throw error; // 配置文件读取失败时不继续后续操作
     }
+    // 检查词典数据库，不可用时仅弹窗报错，在线词典仍然可以使用
+    try {
+        await dict.checkDictDb();
+    } catch (error) {
+        console.error(error);
+        await api.dialog.message(String(error), { title: '词典数据库不可用', kind: 'error' });
+    }
+    // 监听后端对 dict.db 的热重载：替换成功时更新 dictRevision，新文件不可用时弹窗报错（仍使用旧的词典）
+    await api.event.listen('dict-changed', () => {
+        console.info('dict.db has been reloaded');
+        dictRevision.value++;
+    });
+    await api.event.listen<BackendErrorPayload>('dict-invalid', event => {
+        const error = new BackendError(event.payload);
+        console.error(error);
+        void api.dialog.message(String(error), { title: '新的词典数据库不可用', kind: 'error' });
+    });
+    await api.event.listen<BackendErrorPayload>('dict-watcher-error', event => {
+        console.error('Dict watcher error:', new BackendError(event.payload));
+    });
+    // dictionaries 目录中的词典在后台加载，加载完成后仅输出日志
+    await api.event.listen<BackendErrorPayload[]>('dictionaries-loaded', event => {
+        console.info('dictionaries have been loaded');
+        for (const error of event.payload) {
+            console.error('Failed to load dictionary:', new BackendError(error));
+        }
+    });
     // 初始化 AnkiService 对象
     await initAnkiService();
     // 启动配置文件监听器
@@ -0,0 +1,42 @@
+import { invoke } from './utils';
+
+/** 一次查询记录 */
+export interface HistoryEntry {
+    id: number;
+    /** 查询时间，Unix 时间戳（毫秒） */
+    time: number;
+    /** 查询的单词（句子中的原始形式） */
+    word: string;
+    /** 单词的原型，单词本身即为原型或无法确定原型时为 null */
+    base: string | null;
+    /** 单词所在的句子 */
+    sentence: string | null;
+}
+
+/** 按原型统计的查询次数 */
+export interface LookupCount {
+    /** 单词的原型（没有原型时为单词本身的小写形式） */
+    word: string;
+    count: number;
+    /** 最近一次查询的时间，Unix 时间戳（毫秒） */
+    lastTime: number;
+}
+
+/** 记录一次查询，原型由后端从词典中查询 */
+export async function recordLookup(word: string, sentence: string | null = null): Promise<HistoryEntry> {
+    return await invoke<HistoryEntry>('record_lookup', { word, sentence });
+}
+
+/** 最近的查询记录，按时间从新到旧排列 */
+export async function recentLookups(limit?: number): Promise<HistoryEntry[]> {
+    return await invoke<HistoryEntry[]>('recent_lookups', { limit });
+}
+
+/** 查询次数最多的单词（按原型合并） */
+export async function mostLookedUp(limit?: number): Promise<LookupCount[]> {
+    return await invoke<LookupCount[]>('most_looked_up', { limit });
+}
+
+export async function clearHistory(): Promise<void> {
+    await invoke('clear_history');
+}
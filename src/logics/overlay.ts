@@ -0,0 +1,57 @@
+import { invoke } from './utils';
+import type { Grammar, Phonetics } from './dict';
+
+/** 添加、修改词条以及导入导出时使用的词条内容 */
+export interface UserEntryContent {
+    word: string;
+    phonetic?: string | null;
+    sense?: string | null;
+    enDef?: string | null;
+    cnDef?: string | null;
+    notes?: string | null;
+    /** 是否代替自带的词条，为 true 时查询此单词不再显示 Collins、Oxford 中的词条 */
+    replaceBuiltin?: boolean;
+}
+
+/** 用户词典中的一个词条 */
+export interface UserEntry {
+    id: number;
+    word: string;
+    phonetic: string | null;
+    phonetics: Phonetics | null;
+    sense: string | null;
+    enDef: string | null;
+    cnDef: string | null;
+    notes: string | null;
+    replaceBuiltin: boolean;
+    /** 添加或最后修改的时间，Unix 时间戳（毫秒） */
+    time: number;
+    grammar: Grammar | null;
+}
+
+export async function listUserEntries(): Promise<UserEntry[]> {
+    return await invoke<UserEntry[]>('list_user_entries');
+}
+
+export async function addUserEntry(entry: UserEntryContent): Promise<UserEntry> {
+    return await invoke<UserEntry>('add_user_entry', { entry });
+}
+
+export async function updateUserEntry(id: number, entry: UserEntryContent): Promise<UserEntry> {
+    return await invoke<UserEntry>('update_user_entry', { id, entry });
+}
+
+/** 删除用户词典中的词条，返回词条是否存在 */
+export async function deleteUserEntry(id: number): Promise<boolean> {
+    return await invoke<boolean>('delete_user_entry', { id });
+}
+
+/** 从 JSON 文件导入用户词典，已有的相同词条不重复导入，返回实际导入的词条数 */
+export async function importUserEntries(path: string): Promise<number> {
+    return await invoke<number>('import_user_entries', { path });
+}
+
+/** 将用户词典导出为 JSON 文件，返回导出的词条数 */
+export async function exportUserEntries(path: string): Promise<number> {
+    return await invoke<number>('export_user_entries', { path });
+}
@@ -4,11 +4,108 @@ export * as string from './stringutils';
 export { isWord, tokenize, escapeHTML } from './stringutils';
 export * as typing from './typing';
 
+/** 后端错误的类别，与 Rust 中的 `ErrorKind` 对应 */
+export type BackendErrorKind =
+    | 'Io'
+    | 'ConfigMissing'
+    | 'TomlParse'
+    | 'ConfigVersion'
+    | 'ConfigConflict'
+    | 'MissingKey'
+    | 'InvalidValue'
+    | 'Sqlite'
+    | 'InvalidDictionary'
+    | 'NotFound'
+    | 'PlatformUnsupported'
+    | 'Watcher'
+    | 'NotReady'
+    | 'Internal';
+
+/** 文件中的位置，行号和列号都从 1 开始 */
+export interface TextPosition {
+    line: number;
+    column: number;
+}
+
+/** 错误涉及的一处具体问题，如配置文件中某个键的值不正确 */
+export interface BackendIssue {
+    key?: string;
+    message: string;
+    /** 问题在文件中的位置，end 不包含在内 */
+    span?: { start: TextPosition; end: TextPosition; };
+    /** 修正建议 */
+    hint?: string;
+}
+
+/** 后端命令返回的错误的原始格式 */
+export interface BackendErrorPayload {
+    kind: BackendErrorKind;
+    message: string;
+    path?: string;
+    detail?: string;
+    issues?: BackendIssue[];
+}
+
+/** 将问题格式化为 `行:列: 键: 描述 (建议)` 的形式 */
+export function formatIssue(issue: BackendIssue): string {
+    let text = '';
+    if (issue.span != null) {
+        text += `${issue.span.start.line}:${issue.span.start.column}: `;
+    }
+    if (issue.key != null) {
+        text += `${issue.key}: `;
+    }
+    text += issue.message;
+    if (issue.hint != null) {
+        text += ` (${issue.hint})`;
+    }
+    return text;
+}
+
+/** 后端命令返回的错误，可以根据 `kind` 做针对性的处理 */
+export class BackendError extends Error {
+    public readonly kind: BackendErrorKind;
+    /** 与错误相关的文件路径 */
+    public readonly path?: string;
+    /** 底层错误的信息 */
+    public readonly detail?: string;
+    /** 错误涉及的各处具体问题 */
+    public readonly issues: BackendIssue[];
+
+    public constructor(payload: BackendErrorPayload) {
+        let message = payload.message;
+        if (payload.path != null) {
+            message += ` (${payload.path})`;
+        }
+        if (payload.detail != null) {
+            message += `: ${payload.detail}`;
+        }
+        for (const issue of payload.issues ?? []) {
+            message += `\n  ${formatIssue(issue)}`;
+        }
+        super(message);
+        this.name = 'BackendError';
+        this.kind = payload.kind;
+        this.path = payload.path;
+        this.detail = payload.detail;
+        this.issues = payload.issues ?? [];
+    }
+}
+
+export function isBackendErrorPayload(value: unknown): value is BackendErrorPayload {
+    return typeof value === 'object' && value != null
+        && typeof (value as BackendErrorPayload).kind === 'string'
+        && typeof (value as BackendErrorPayload).message === 'string';
+}
+
 export async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
     try {
         return await api.core.invoke<T>(cmd, args);
     } catch (error) {
-        throw (typeof error === 'string') ? new Error(error) : error;
+        if (typeof error === 'string') {
+            throw new Error(error);
+        }
+        throw isBackendErrorPayload(error) ? new BackendError(error) : error;
     }
 }
 
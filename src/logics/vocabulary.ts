@@ -0,0 +1,40 @@
+import { invoke } from './utils';
+
+/** 词元的分类：熟词、生词、词典中没有的词（包括标点、数字等） */
+export type TokenStatus = 'known' | 'unknown' | 'not-in-dictionary';
+
+export interface TokenClass {
+    token: string;
+    /** 熟词表中使用的原型，词典中没有此单词时为 null */
+    base: string | null;
+    status: TokenStatus;
+}
+
+/** 将单词的原型加入熟词表，返回新加入的原型 */
+export async function addKnownWords(words: string[]): Promise<string[]> {
+    return await invoke<string[]>('add_known_words', { words });
+}
+
+/** 将单词的原型从熟词表中移除，返回实际移除的原型 */
+export async function removeKnownWords(words: string[]): Promise<string[]> {
+    return await invoke<string[]>('remove_known_words', { words });
+}
+
+export async function listKnownWords(): Promise<string[]> {
+    return await invoke<string[]>('list_known_words');
+}
+
+/** 从纯文本文件导入熟词（每行一个单词），返回新加入的原型 */
+export async function importKnownWords(path: string): Promise<string[]> {
+    return await invoke<string[]>('import_known_words', { path });
+}
+
+/** 将熟词表导出为纯文本文件（每行一个单词），返回导出的单词数 */
+export async function exportKnownWords(path: string): Promise<number> {
+    return await invoke<number>('export_known_words', { path });
+}
+
+/** 将句子中的词元分为熟词、生词和词典中没有的词 */
+export async function classifyTokens(tokens: string[]): Promise<TokenClass[]> {
+    return await invoke<TokenClass[]>('classify_tokens', { tokens });
+}